@@ -0,0 +1,193 @@
+//! 带前缀长度的 IP 地址。
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+/// 接口地址，例如 `10.0.0.1/24` 或 `fd00::1/64`。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    pub addr: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl IpCidr {
+    fn max_prefix_len(addr: IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// 检查地址能否分配给接口。
+    fn validate(&self) -> Result<(), String> {
+        let max = Self::max_prefix_len(self.addr);
+        if self.prefix_len == 0 || self.prefix_len > max {
+            return Err(format!(
+                "地址 {} 的前缀长度 {} 无效，应为 1~{}",
+                self.addr, self.prefix_len, max
+            ));
+        }
+        if self.addr.is_unspecified() || self.addr.is_multicast() || self.addr.is_loopback() {
+            return Err(format!(
+                "{} 是未指定、组播或环回地址，不能分配给接口",
+                self.addr
+            ));
+        }
+        if let IpAddr::V4(v4) = self.addr {
+            // /31 和 /32 没有网络地址和广播地址
+            if self.prefix_len <= 30 {
+                let mask = u32::MAX << (32 - self.prefix_len);
+                let host = u32::from(v4) & !mask;
+                if host == 0 || host == !mask {
+                    return Err(format!(
+                        "{} 是网段 {}/{} 的网络地址或广播地址",
+                        v4,
+                        Ipv4Addr::from(u32::from(v4) & mask),
+                        self.prefix_len
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    /// 解析 `地址/前缀长度`，省略前缀长度时视为主机地址 (/32 或 /128)。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("无效的IP地址 '{}': {}", addr, e))?;
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .parse()
+                .map_err(|_| format!("无效的前缀长度 '{}'", len))?,
+            None => Self::max_prefix_len(addr),
+        };
+        let cidr = IpCidr { addr, prefix_len };
+        cidr.validate()?;
+        Ok(cidr)
+    }
+}
+
+impl Serialize for IpCidr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 解析 IPv4 接口地址，例如 `10.0.0.1/24`。
+pub fn parse_ipv4_cidr(s: &str) -> Result<IpCidr, String> {
+    let cidr: IpCidr = s.parse()?;
+    if !cidr.addr.is_ipv4() {
+        return Err(format!("'{}' 不是 IPv4 地址", s));
+    }
+    Ok(cidr)
+}
+
+/// 解析 IPv6 接口地址，例如 `fd00::1/64`。
+pub fn parse_ipv6_cidr(s: &str) -> Result<IpCidr, String> {
+    let cidr: IpCidr = s.parse()?;
+    if !cidr.addr.is_ipv6() {
+        return Err(format!("'{}' 不是 IPv6 地址", s));
+    }
+    Ok(cidr)
+}
+
+/// 检查地址列表中是否有重复的地址 (不论前缀长度是否相同)。
+pub fn check_duplicates(addresses: &[IpCidr]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for cidr in addresses {
+        if !seen.insert(cidr.addr) {
+            bail!("地址 {} 被重复指定", cidr.addr);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> IpCidr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_prefix_lengths() {
+        assert!("10.0.0.1/0".parse::<IpCidr>().is_err());
+        assert!("10.0.0.1/33".parse::<IpCidr>().is_err());
+        assert!("fd00::1/0".parse::<IpCidr>().is_err());
+        assert!("fd00::1/129".parse::<IpCidr>().is_err());
+        assert!("10.0.0.1/x".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn rejects_network_and_broadcast_addresses() {
+        assert!("10.0.0.0/24".parse::<IpCidr>().is_err());
+        assert!("10.0.0.255/24".parse::<IpCidr>().is_err());
+        assert_eq!(cidr("10.0.0.1/24").prefix_len, 24);
+    }
+
+    #[test]
+    fn allows_any_host_in_point_to_point_prefixes() {
+        assert_eq!(cidr("10.0.0.0/31").prefix_len, 31);
+        assert_eq!(cidr("10.0.0.1/31").prefix_len, 31);
+        assert_eq!(cidr("10.0.0.0/32").prefix_len, 32);
+        assert_eq!(cidr("10.0.0.255/32").prefix_len, 32);
+    }
+
+    #[test]
+    fn defaults_to_host_prefix() {
+        assert_eq!(
+            cidr("10.0.0.1"),
+            IpCidr {
+                addr: "10.0.0.1".parse().unwrap(),
+                prefix_len: 32
+            }
+        );
+        assert_eq!(
+            cidr("fd00::1"),
+            IpCidr {
+                addr: "fd00::1".parse().unwrap(),
+                prefix_len: 128
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_family() {
+        assert!(parse_ipv4_cidr("fd00::1/64").is_err());
+        assert!(parse_ipv6_cidr("10.0.0.1/24").is_err());
+    }
+
+    #[test]
+    fn duplicates_ignore_prefix_length() {
+        assert!(check_duplicates(&[cidr("10.0.0.1/24"), cidr("10.0.0.2/24")]).is_ok());
+        assert!(check_duplicates(&[cidr("10.0.0.1/24"), cidr("10.0.0.1/32")]).is_err());
+        assert!(check_duplicates(&[cidr("fd00::1/64"), cidr("fd00::1/128")]).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! 用户态 ARP 应答: 让内核把 TAP 另一端当作一台拥有指定 IPv4 地址的主机。
+
+use crate::decode::{parse_arp, parse_ethernet};
+use crate::mac::MacAddr;
+use crate::packet::{self, ARP_REPLY, ARP_REQUEST, ETHERTYPE_ARP};
+use crate::queue::FrameHandler;
+use anyhow::Result;
+use log::info;
+use std::net::Ipv4Addr;
+use tokio::sync::watch;
+
+/// 以设备的 MAC 地址应答对 `addresses` 的 ARP 请求，并在启动时为它们发送免费 ARP。
+///
+/// 设备地址由 `mac` 提供，它随接口变化通知更新，因此任何方式更换 MAC 后应答中的地址都随之更新。
+pub struct ArpResponder {
+    mac: watch::Receiver<MacAddr>,
+    addresses: Vec<Ipv4Addr>,
+}
+
+impl ArpResponder {
+    pub fn new(mac: watch::Receiver<MacAddr>, addresses: &[Ipv4Addr]) -> Self {
+        ArpResponder {
+            mac,
+            addresses: addresses.to_vec(),
+        }
+    }
+
+    fn mac(&self) -> MacAddr {
+        *self.mac.borrow()
+    }
+}
+
+impl FrameHandler for ArpResponder {
+    fn handle(&self, frame: &[u8]) -> Result<Option<Vec<u8>>> {
+        let Some(eth) = parse_ethernet(frame) else {
+            return Ok(None);
+        };
+        if eth.ethertype != ETHERTYPE_ARP || !eth.vlans.is_empty() {
+            return Ok(None);
+        }
+        let Some(request) = parse_arp(eth.payload) else {
+            return Ok(None);
+        };
+        // 免费 ARP 的发送方与目标相同，不需要应答
+        if request.op != ARP_REQUEST
+            || request.sender_ip == request.target_ip
+            || !self.addresses.contains(&request.target_ip)
+        {
+            return Ok(None);
+        }
+
+        let mac = self.mac();
+        info!(
+            "应答 ARP 请求: {} is-at {} (询问方 {} {})",
+            request.target_ip, mac, request.sender_ip, request.sender_mac
+        );
+        Ok(Some(packet::arp(
+            request.sender_mac,
+            ARP_REPLY,
+            mac,
+            request.target_ip,
+            request.sender_mac,
+            request.sender_ip,
+        )))
+    }
+
+    fn startup(&self) -> Result<Vec<Vec<u8>>> {
+        let mac = self.mac();
+        info!(
+            "以MAC地址 {} 应答对 {} 的 ARP 请求",
+            mac,
+            self.addresses
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(self
+            .addresses
+            .iter()
+            .map(|&ip| packet::gratuitous_arp(mac, ip))
+            .collect())
+    }
+}
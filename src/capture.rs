@@ -0,0 +1,288 @@
+//! 把设备上读到的帧写入 pcapng (或经典 pcap) 文件，供 Wireshark 打开。
+
+use crate::layer::DeviceLayer;
+use crate::mac::MacAddr;
+use crate::queue::FrameHandler;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 以太网帧。
+const LINKTYPE_ETHERNET: u16 = 1;
+/// 没有链路层头部的裸 IPv4/IPv6 包 (TUN 设备)。
+const LINKTYPE_RAW: u16 = 101;
+
+/// 每帧最多保存的字节数。
+const SNAPLEN: u32 = 65535;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const OPT_END: u16 = 0;
+const SHB_USERAPPL: u16 = 4;
+const IF_NAME: u16 = 2;
+const IF_MAC_ADDR: u16 = 6;
+const IF_TSRESOL: u16 = 9;
+
+/// 经典 pcap 的微秒精度魔数。
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+
+/// 向 pcapng 块中追加一个选项，值按 4 字节对齐补零。
+fn push_option(block: &mut Vec<u8>, code: u16, value: &[u8]) {
+    block.extend_from_slice(&code.to_le_bytes());
+    block.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    block.extend_from_slice(value);
+    block.resize(block.len().next_multiple_of(4), 0);
+}
+
+/// 给块体加上块类型和首尾两份总长度，得到完整的 pcapng 块。
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let total = (body.len() + 12) as u32;
+    let mut block = Vec::with_capacity(total as usize);
+    block.extend_from_slice(&block_type.to_le_bytes());
+    block.extend_from_slice(&total.to_le_bytes());
+    block.extend_from_slice(body);
+    block.extend_from_slice(&total.to_le_bytes());
+    block
+}
+
+/// 捕获文件的格式，由扩展名决定: `.pcap` 为经典 pcap，其他为 pcapng。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Pcap,
+    Pcapng,
+}
+
+/// 正在写入的捕获文件。多个队列共享同一个文件，每写一帧刷新一次。
+pub struct Capture {
+    format: Format,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl Capture {
+    /// 创建捕获文件并写入文件头。
+    ///
+    /// pcapng 的接口描述块中记录接口名和创建时的 MAC 地址；之后通过控制套接字或轮换更换的地址不会反映在文件中。
+    pub fn create(
+        path: &Path,
+        layer: DeviceLayer,
+        interface: &str,
+        mac: Option<MacAddr>,
+    ) -> Result<Self> {
+        let format = if path.extension().is_some_and(|ext| ext == "pcap") {
+            Format::Pcap
+        } else {
+            Format::Pcapng
+        };
+        let linktype = match layer {
+            DeviceLayer::L2 => LINKTYPE_ETHERNET,
+            DeviceLayer::L3 => LINKTYPE_RAW,
+        };
+        let file = File::create(path)
+            .with_context(|| format!("创建捕获文件 '{}' 失败", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let header = match format {
+            Format::Pcap => pcap_header(linktype),
+            Format::Pcapng => pcapng_header(linktype, interface, mac),
+        };
+        writer
+            .write_all(&header)
+            .and_then(|()| writer.flush())
+            .with_context(|| format!("写入捕获文件 '{}' 失败", path.display()))?;
+        Ok(Capture {
+            format,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// 以当前时间写入一帧。
+    pub fn write(&self, frame: &[u8]) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let captured = &frame[..frame.len().min(SNAPLEN as usize)];
+        let record = match self.format {
+            Format::Pcap => {
+                let mut record = Vec::with_capacity(16 + captured.len());
+                record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+                record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+                record.extend_from_slice(&(captured.len() as u32).to_le_bytes());
+                record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+                record.extend_from_slice(captured);
+                record
+            }
+            Format::Pcapng => {
+                // 接口描述块中声明了纳秒精度
+                let nanos = timestamp.as_nanos() as u64;
+                let mut body = Vec::with_capacity(20 + captured.len() + 3);
+                body.extend_from_slice(&0u32.to_le_bytes());
+                body.extend_from_slice(&((nanos >> 32) as u32).to_le_bytes());
+                body.extend_from_slice(&(nanos as u32).to_le_bytes());
+                body.extend_from_slice(&(captured.len() as u32).to_le_bytes());
+                body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+                body.extend_from_slice(captured);
+                body.resize(body.len().next_multiple_of(4), 0);
+                pcapng_block(PCAPNG_ENHANCED_PACKET, &body)
+            }
+        };
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(&record)?;
+        writer.flush()
+    }
+}
+
+impl FrameHandler for Capture {
+    fn handle(&self, frame: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.write(frame).context("写入捕获文件失败")?;
+        Ok(None)
+    }
+}
+
+/// 经典 pcap 的全局文件头。
+fn pcap_header(linktype: u16) -> Vec<u8> {
+    let mut header = Vec::with_capacity(24);
+    header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes());
+    header.extend_from_slice(&4u16.to_le_bytes());
+    header.extend_from_slice(&0i32.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&SNAPLEN.to_le_bytes());
+    header.extend_from_slice(&u32::from(linktype).to_le_bytes());
+    header
+}
+
+/// pcapng 的节头块和唯一的接口描述块。
+fn pcapng_header(linktype: u16, interface: &str, mac: Option<MacAddr>) -> Vec<u8> {
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+    shb.extend_from_slice(&1u16.to_le_bytes());
+    shb.extend_from_slice(&0u16.to_le_bytes());
+    // 节长度未知
+    shb.extend_from_slice(&(-1i64).to_le_bytes());
+    let application = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+    push_option(&mut shb, SHB_USERAPPL, application.as_bytes());
+    push_option(&mut shb, OPT_END, &[]);
+
+    let mut idb = Vec::new();
+    idb.extend_from_slice(&linktype.to_le_bytes());
+    idb.extend_from_slice(&0u16.to_le_bytes());
+    idb.extend_from_slice(&SNAPLEN.to_le_bytes());
+    push_option(&mut idb, IF_NAME, interface.as_bytes());
+    if let Some(mac) = mac {
+        push_option(&mut idb, IF_MAC_ADDR, &mac.octets());
+    }
+    // 时间戳以纳秒为单位
+    push_option(&mut idb, IF_TSRESOL, &[9]);
+    push_option(&mut idb, OPT_END, &[]);
+
+    let mut header = pcapng_block(PCAPNG_SECTION_HEADER, &shb);
+    header.extend(pcapng_block(PCAPNG_INTERFACE_DESCRIPTION, &idb));
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const MAC: MacAddr = MacAddr::new([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn temp_path(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "tap_mac_addr_test-{}.{}",
+            std::process::id(),
+            extension
+        ))
+    }
+
+    /// 把 pcapng 文件拆成 (块类型, 块体)，同时检查每个块首尾的长度一致且按 4 字节对齐。
+    fn blocks(mut data: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut blocks = Vec::new();
+        while !data.is_empty() {
+            let total = u32_at(data, 4) as usize;
+            assert_eq!(total % 4, 0);
+            assert_eq!(u32_at(data, total - 4) as usize, total);
+            blocks.push((u32_at(data, 0), &data[8..total - 4]));
+            data = &data[total..];
+        }
+        blocks
+    }
+
+    /// 块体中从 `offset` 开始的选项 (代码, 值)。
+    fn options(body: &[u8], mut offset: usize) -> Vec<(u16, &[u8])> {
+        let mut options = Vec::new();
+        loop {
+            let code = u16_at(body, offset);
+            let len = usize::from(u16_at(body, offset + 2));
+            if code == OPT_END {
+                return options;
+            }
+            options.push((code, &body[offset + 4..offset + 4 + len]));
+            offset += 4 + len.next_multiple_of(4);
+        }
+    }
+
+    #[test]
+    fn writes_pcapng_blocks() {
+        let path = temp_path("pcapng");
+        let frame: Vec<u8> = (0..61).collect();
+        let capture = Capture::create(&path, DeviceLayer::L2, "tap0", Some(MAC)).unwrap();
+        capture.write(&frame).unwrap();
+        drop(capture);
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let blocks = blocks(&data);
+        let types: Vec<_> = blocks.iter().map(|(block_type, _)| *block_type).collect();
+        assert_eq!(
+            types,
+            [
+                PCAPNG_SECTION_HEADER,
+                PCAPNG_INTERFACE_DESCRIPTION,
+                PCAPNG_ENHANCED_PACKET
+            ]
+        );
+
+        let shb = blocks[0].1;
+        assert_eq!(u32_at(shb, 0), PCAPNG_BYTE_ORDER_MAGIC);
+
+        let idb = blocks[1].1;
+        assert_eq!(u16_at(idb, 0), LINKTYPE_ETHERNET);
+        let idb_options = options(idb, 8);
+        assert!(idb_options.contains(&(IF_NAME, b"tap0".as_slice())));
+        assert!(idb_options.contains(&(IF_MAC_ADDR, MAC.octets().as_slice())));
+
+        let epb = blocks[2].1;
+        assert_eq!(u32_at(epb, 12), 61);
+        assert_eq!(u32_at(epb, 16), 61);
+        assert_eq!(&epb[20..81], frame.as_slice());
+        // 61 字节的帧补齐到 64 字节
+        assert_eq!(&epb[81..], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn omits_mac_option_without_mac() {
+        let path = temp_path("l3.pcapng");
+        drop(Capture::create(&path, DeviceLayer::L3, "tun0", None).unwrap());
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let idb = blocks(&data)[1].1;
+        assert_eq!(u16_at(idb, 0), LINKTYPE_RAW);
+        assert!(options(idb, 8).iter().all(|(code, _)| *code != IF_MAC_ADDR));
+    }
+}
@@ -0,0 +1,88 @@
+//! 在运行中的设备上更换MAC地址。
+
+use crate::addr::IpCidr;
+use crate::mac::MacAddr;
+use crate::netlink::Netlink;
+use crate::packet;
+use crate::slaac::is_link_local;
+use crate::sys;
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use std::net::IpAddr;
+
+/// 更换接口 `name` 的MAC地址，返回更换前的地址。
+///
+/// 接口处于启用状态时按 禁用 → 设置地址 → 启用 的顺序操作，完成后从接口发出
+/// 免费 ARP 和主动 NA，让邻居更新缓存。
+pub async fn change_mac(
+    netlink: &Netlink,
+    name: &str,
+    new_mac: MacAddr,
+) -> Result<Option<MacAddr>> {
+    let link = netlink.link_by_name(name).await?;
+    let was_up = link.flags & libc::IFF_UP as u32 != 0;
+    let addresses_before = netlink.addresses(link.index).await?;
+
+    if was_up {
+        netlink.set_up(link.index, false).await?;
+    }
+    let result = netlink.set_address(link.index, new_mac).await;
+    // 即使设置失败也要恢复接口原来的状态
+    if was_up {
+        netlink.set_up(link.index, true).await?;
+    }
+    result?;
+
+    let actual = sys::hardware_address(name)
+        .with_context(|| format!("读取设备 '{}' 的硬件地址失败", name))?;
+    if actual != new_mac {
+        bail!("MAC地址未生效: 请求 {}，内核报告 {}", new_mac, actual);
+    }
+
+    if was_up {
+        // 内核在禁用接口时会清除 IPv6 地址 (除非开启了 keep_addr_on_down)。
+        // 链路本地地址由内核按新的MAC地址重新生成，其余地址需要重新添加。
+        let addresses_after = netlink.addresses(link.index).await?;
+        for lost in addresses_before
+            .iter()
+            .filter(|a| !addresses_after.iter().any(|b| b.address == a.address))
+        {
+            if let IpAddr::V6(v6) = lost.address {
+                if is_link_local(&v6) {
+                    continue;
+                }
+            }
+            let cidr = IpCidr {
+                addr: lost.address,
+                prefix_len: lost.prefix_len,
+            };
+            match netlink.add_address(link.index, cidr).await {
+                Ok(()) => info!("已重新添加禁用接口时被内核移除的地址 {}", cidr),
+                Err(e) => warn!(
+                    "重新添加地址 {} 失败: {:#} (参见 net.ipv6.conf.{}.keep_addr_on_down)",
+                    cidr, e, name
+                ),
+            }
+        }
+        let sent = announce(netlink, link.index, new_mac).await?;
+        info!("已为 {} 个地址发送免费 ARP / 主动 NA", sent);
+    }
+    Ok(link.mac)
+}
+
+/// 为接口上的每个 IPv4 地址发送免费 ARP，为每个 IPv6 地址发送主动 NA，返回成功发送的数量。
+pub async fn announce(netlink: &Netlink, index: u32, mac: MacAddr) -> Result<usize> {
+    let addresses = netlink.addresses(index).await?;
+    let mut sent = 0;
+    for addr in addresses {
+        let frame = match addr.address {
+            IpAddr::V4(ip) => packet::gratuitous_arp(mac, ip),
+            IpAddr::V6(ip) => packet::unsolicited_na(mac, ip),
+        };
+        match sys::send_frame(index, &frame) {
+            Ok(()) => sent += 1,
+            Err(e) => warn!("为 {} 发送通告失败: {}", addr.address, e),
+        }
+    }
+    Ok(sent)
+}
@@ -0,0 +1,436 @@
+//! 命令行参数定义。
+
+use crate::DEFAULT_TAP_NAME;
+use anyhow::{anyhow, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgGroup, ArgMatches, Args, FromArgMatches, Parser, Subcommand};
+use log::info;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+use tap_mac_addr_test::addr::{parse_ipv4_cidr, parse_ipv6_cidr, IpCidr};
+use tap_mac_addr_test::config::{parse_interval, ConfigFile, DeviceConfig};
+use tap_mac_addr_test::info::OutputFormat;
+use tap_mac_addr_test::layer::DeviceLayer;
+use tap_mac_addr_test::list::ListFormat;
+use tap_mac_addr_test::mac::{parse_mac_address, MacAddr, MacFormat, MacPolicy};
+use tap_mac_addr_test::mtu::{DEFAULT_MTU, JUMBO_MTU};
+use tap_mac_addr_test::oui::MacPrefix;
+use tap_mac_addr_test::tun::{parse_group, parse_user};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 创建设备并保持运行，直到按下 Ctrl+C (使用 --persist 时创建后立即退出)
+    Create(Box<CreateArgs>),
+    /// 删除用 --persist 创建的持久设备
+    Delete {
+        /// 要删除的设备名称
+        name: String,
+    },
+    /// 列出所有 TAP/TUN 设备及其 MAC 地址、MTU、所有者和标志
+    List {
+        /// MAC 地址的输出格式
+        #[arg(long, default_value = "colon")]
+        mac_format: MacFormat,
+
+        /// 列表的输出格式
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        output: ListFormat,
+    },
+    /// 显示设备的详细信息
+    Show {
+        /// 设备名称
+        name: String,
+
+        #[command(flatten)]
+        display: DisplayArgs,
+    },
+    /// 修改已存在设备的 MAC 地址或 MTU
+    Set(SetArgs),
+    /// 检查配置文件中的设备定义，不创建任何设备
+    CheckConfig {
+        /// 配置文件路径
+        path: PathBuf,
+    },
+}
+
+/// 设备信息的显示方式。
+#[derive(Args, Clone, Debug)]
+pub struct DisplayArgs {
+    /// MAC 地址的输出格式: colon, dash, dotted, bare，可追加 -upper 或 -lower
+    #[arg(long, default_value = "colon")]
+    pub mac_format: MacFormat,
+
+    /// 设备信息的输出格式
+    #[arg(long, value_enum, default_value_t = OutputFormat::Ip)]
+    pub output: OutputFormat,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct CreateArgs {
+    /// 从 TOML 配置文件读取设备定义，命令行上显式给出的选项优先。
+    /// 默认创建文件中的所有设备，指定 --name 时只创建其中一个
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// 定义一个设备，可重复指定 (例如: name=tap1,mac=52:54:00:12:34:56,mtu=1400)。
+    /// 键与配置文件的字段相同，其值优先于其他命令行选项。
+    /// 没有 --config 时，显式给出 --name 的命令行设备也会一起创建，否则只创建这里定义的设备
+    #[arg(long, value_name = "KEY=VALUE,...")]
+    pub device: Vec<DeviceConfig>,
+
+    /// 设备的名称
+    #[arg(long, default_value = DEFAULT_TAP_NAME)]
+    pub name: String,
+
+    /// 设备工作的层: l2 创建 TAP 设备，l3 创建 TUN 设备。
+    /// MAC 相关的选项只适用于 l2。
+    #[arg(long, value_enum, default_value_t = DeviceLayer::L2)]
+    pub layer: DeviceLayer,
+
+    /// 设备的队列数。大于 1 时以 IFF_MULTI_QUEUE 创建设备，每个队列由一个任务读取
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub queues: u16,
+
+    /// 定期打印每个队列的收包计数 (例如: 10s)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_interval)]
+    pub stats_interval: Option<Duration>,
+
+    /// 设备的 MTU (最大传输单元)，默认 1500，使用 --jumbo 时默认 9000
+    #[arg(long)]
+    pub mtu: Option<u16>,
+
+    /// 允许超过 1500 的巨型帧 MTU
+    #[arg(long)]
+    pub jumbo: bool,
+
+    /// TAP 设备的 MAC 地址 (例如: 0a:0b:0c:0d:0e:0f、0a0b.0c0d.0e0f 或 0a0b0c0d0e0f)
+    /// 如果未提供，将生成一个随机的本地管理地址。
+    #[arg(long, value_parser = parse_mac_address)]
+    pub mac: Option<MacAddr>,
+
+    /// 由种子确定性地派生 MAC 地址，每次运行都得到相同的地址。
+    /// 不带值时使用接口名加本机 machine-id 作为种子。
+    #[arg(long, value_name = "SEED", num_args = 0..=1, default_missing_value = "", conflicts_with = "mac")]
+    pub mac_seed: Option<String>,
+
+    /// 生成 MAC 地址时保留的厂商前缀 (例如 52:54:00)，只随机剩余字节。
+    /// 也可使用预设名称: qemu, kvm, xen, virtualbox, vmware, hyperv, parallels, docker
+    #[arg(long, value_name = "PREFIX", conflicts_with = "mac")]
+    pub mac_prefix: Option<MacPrefix>,
+
+    /// MAC 状态文件 (.toml 或 .json)。未提供 --mac 时，首次运行生成的地址会按接口名记录在此，
+    /// 之后的运行直接复用。
+    #[arg(long, value_name = "PATH")]
+    pub mac_state: Option<PathBuf>,
+
+    /// 对组播、广播、全零等不可用 MAC 地址的处理策略
+    #[arg(long, value_enum, default_value_t = MacPolicy::Strict)]
+    pub mac_policy: MacPolicy,
+
+    /// 自动修正不可用的 MAC 地址 (置上本地管理位并清除组播位)
+    #[arg(long)]
+    pub mac_fix: bool,
+
+    /// 设备创建后分配的 IPv4 地址，可重复指定 (例如: 10.0.0.1/24)
+    #[arg(long = "ipv4", value_name = "ADDR/PREFIX", value_parser = parse_ipv4_cidr)]
+    pub ipv4: Vec<IpCidr>,
+
+    /// 设备创建后分配的 IPv6 地址，可重复指定 (例如: fd00::1/64)
+    #[arg(long = "ipv6", value_name = "ADDR/PREFIX", value_parser = parse_ipv6_cidr)]
+    pub ipv6: Vec<IpCidr>,
+
+    /// 手动分配由 MAC 地址派生的 EUI-64 链路本地地址 (fe80::/64)
+    #[arg(long)]
+    pub assign_link_local: bool,
+
+    /// 以设备的 MAC 地址应答对该 IPv4 地址的 ARP 请求，并在启动时发送免费 ARP，可重复指定。
+    /// 地址应与设备位于同一网段但不分配给设备本身 (例如: --ipv4 10.0.0.1/24 --respond-ipv4 10.0.0.2)
+    #[arg(long, value_name = "ADDR")]
+    pub respond_ipv4: Vec<Ipv4Addr>,
+
+    /// 以设备的 MAC 地址应答对该 IPv6 地址的邻居请求 (包括 DAD 探测)，并在启动时发送主动 NA，
+    /// 可重复指定 (例如: --ipv6 fd00::1/64 --respond-ipv6 fd00::2)
+    #[arg(long, value_name = "ADDR")]
+    pub respond_ipv6: Vec<Ipv6Addr>,
+
+    /// 运行时控制套接字的路径，可通过它更换运行中设备的 MAC 地址
+    /// (例如: echo "mac random" | socat - UNIX-CONNECT:/run/tap0.sock)
+    #[arg(long, value_name = "PATH")]
+    pub control: Option<PathBuf>,
+
+    /// 定期为运行中的设备更换新的随机本地管理 MAC 地址 (例如: 30s, 5m, 1h)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_interval)]
+    pub rotate_mac: Option<Duration>,
+
+    /// 以 JSON Lines 格式追加记录每次 MAC 轮换的文件
+    #[arg(long, value_name = "PATH", requires = "rotate_mac")]
+    pub mac_history: Option<PathBuf>,
+
+    /// 把设备上读到的每一帧写入捕获文件，扩展名为 .pcap 时写经典 pcap，否则写 pcapng
+    #[arg(long, value_name = "PATH")]
+    pub capture: Option<PathBuf>,
+
+    /// 在日志中逐帧打印摘要 (MAC、VLAN、ARP、IP 地址、协议和端口)，
+    /// 源 MAC 为设备自身地址的帧以 `*` 标出
+    #[arg(long)]
+    pub dump: bool,
+
+    /// 配合 --dump 同时打印每帧的十六进制转储
+    #[arg(short = 'x', long, requires = "dump")]
+    pub hex: bool,
+
+    /// 将设备设为持久设备: 程序在完成配置后立即退出，设备保留给其他进程使用，
+    /// 之后用 `delete` 子命令删除
+    #[arg(long, conflicts_with_all = ["control", "rotate_mac", "stats_interval", "capture", "dump", "respond_ipv4", "respond_ipv6"])]
+    pub persist: bool,
+
+    /// 持久设备的所有者 (用户名或 UID)，该用户无需 CAP_NET_ADMIN 即可附加到设备
+    #[arg(long, value_name = "USER", value_parser = parse_user, requires = "persist")]
+    pub owner: Option<u32>,
+
+    /// 持久设备所属的组 (组名或 GID)，组内成员无需 CAP_NET_ADMIN 即可附加到设备
+    #[arg(long, value_name = "GROUP", value_parser = parse_group, requires = "persist")]
+    pub group: Option<u32>,
+
+    #[command(flatten)]
+    pub display: DisplayArgs,
+}
+
+/// 选项 `id` 是否在命令行上显式给出。
+fn from_command_line(matches: Option<&ArgMatches>, id: &str) -> bool {
+    matches.is_some_and(|m| m.value_source(id) == Some(ValueSource::CommandLine))
+}
+
+impl CreateArgs {
+    /// 所有选项都取默认值的参数。
+    pub fn defaults() -> Self {
+        let matches = Self::augment_args(clap::Command::new("create")).get_matches_from(["create"]);
+        Self::from_arg_matches(&matches).expect("默认参数总是有效的")
+    }
+
+    /// 最终使用的 MTU。
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+            .unwrap_or(if self.jumbo { JUMBO_MTU } else { DEFAULT_MTU })
+    }
+
+    /// 按 --config 和 --device 展开成每个设备各自的参数。两者都没有时只有命令行定义的一个设备。
+    /// 没有 --config 但显式给出了 --name 时，命令行定义的设备排在 `--device` 定义的设备之前。
+    ///
+    /// 配置文件中的值不会覆盖命令行上显式给出的选项；`--device` 中的值则优先于其他命令行选项。
+    pub fn expand(&self, matches: &ArgMatches) -> Result<Vec<CreateArgs>> {
+        let mut devices = Vec::new();
+        if self.config.is_none() && from_command_line(Some(matches), "name") {
+            let mut args = self.clone();
+            args.device.clear();
+            devices.push(args);
+        }
+
+        let mut definitions = Vec::new();
+        if let Some(path) = &self.config {
+            let config = ConfigFile::load(path)?;
+            let selected = if from_command_line(Some(matches), "name") {
+                vec![config.get(&self.name)?.clone()]
+            } else {
+                config.devices
+            };
+            for device in selected {
+                info!(
+                    "使用配置文件 '{}' 中设备 '{}' 的定义",
+                    path.display(),
+                    device.name
+                );
+                definitions.push((device, Some(matches)));
+            }
+        }
+        definitions.extend(self.device.iter().map(|device| (device.clone(), None)));
+
+        if definitions.is_empty() {
+            return Ok(vec![self.clone()]);
+        }
+        for (device, matches) in definitions {
+            let mut args = self.clone();
+            args.device.clear();
+            args.apply_config(&device, matches)
+                .with_context(|| format!("设备 '{}' 的定义无效", device.name))?;
+            devices.push(args);
+        }
+        Ok(devices)
+    }
+
+    /// 用 `config` 填充命令行上没有显式给出的选项。
+    pub fn apply_config(
+        &mut self,
+        config: &DeviceConfig,
+        matches: Option<&ArgMatches>,
+    ) -> Result<()> {
+        macro_rules! merge {
+            ($target:expr, $($field:ident),* $(,)?) => {$(
+                if !from_command_line(matches, stringify!($field)) {
+                    if let Some(value) = config.$field.clone() {
+                        $target.$field = value.into();
+                    }
+                }
+            )*};
+        }
+        merge!(
+            self,
+            layer,
+            queues,
+            stats_interval,
+            mtu,
+            jumbo,
+            mac,
+            mac_seed,
+            mac_prefix,
+            mac_state,
+            mac_policy,
+            mac_fix,
+            assign_link_local,
+            control,
+            rotate_mac,
+            mac_history,
+            capture,
+            dump,
+            hex,
+            persist,
+        );
+        merge!(self.display, mac_format, output);
+
+        self.name = config.name.clone();
+        if !from_command_line(matches, "ipv4") && !config.ipv4.is_empty() {
+            self.ipv4 = config.ipv4.clone();
+        }
+        if !from_command_line(matches, "ipv6") && !config.ipv6.is_empty() {
+            self.ipv6 = config.ipv6.clone();
+        }
+        if !from_command_line(matches, "respond_ipv4") && !config.respond_ipv4.is_empty() {
+            self.respond_ipv4 = config.respond_ipv4.clone();
+        }
+        if !from_command_line(matches, "respond_ipv6") && !config.respond_ipv6.is_empty() {
+            self.respond_ipv6 = config.respond_ipv6.clone();
+        }
+        if !from_command_line(matches, "owner") {
+            if let Some(owner) = &config.owner {
+                self.owner = Some(parse_user(owner).map_err(|e| anyhow!(e))?);
+            }
+        }
+        if !from_command_line(matches, "group") {
+            if let Some(group) = &config.group {
+                self.group = Some(parse_group(group).map_err(|e| anyhow!(e))?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("change").required(true).multiple(true)))]
+pub struct SetArgs {
+    /// 设备名称
+    pub name: String,
+
+    /// 新的 MAC 地址
+    #[arg(long, group = "change", value_parser = parse_mac_address)]
+    pub mac: Option<MacAddr>,
+
+    /// 更换为新的随机本地管理 MAC 地址
+    #[arg(long, group = "change", conflicts_with = "mac")]
+    pub random_mac: bool,
+
+    /// 新的 MTU
+    #[arg(long, group = "change")]
+    pub mtu: Option<u16>,
+
+    /// 允许超过 1500 的巨型帧 MTU
+    #[arg(long, requires = "mtu")]
+    pub jumbo: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use std::fs;
+
+    /// 像 main 一样解析 `create` 的参数并展开成每个设备的参数。
+    fn expand(args: &[&str]) -> Vec<CreateArgs> {
+        let matches = Cli::command()
+            .try_get_matches_from(["tap_mac_addr_test", "create"].iter().chain(args))
+            .unwrap();
+        let Command::Create(create) = Cli::from_arg_matches(&matches).unwrap().command else {
+            unreachable!()
+        };
+        create
+            .expand(matches.subcommand_matches("create").unwrap())
+            .unwrap()
+    }
+
+    /// 写入一个临时配置文件，返回其路径。
+    fn config_file(tag: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "tap_mac_addr_test-{}-{}.toml",
+            std::process::id(),
+            tag
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let path = config_file(
+            "cli",
+            "[[device]]\nname = \"tap1\"\nmtu = 1400\nqueues = 2\n",
+        );
+        let devices = expand(&["--config", path.to_str().unwrap(), "--mtu", "1300"]);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "tap1");
+        assert_eq!(devices[0].mtu, Some(1300));
+        assert_eq!(devices[0].queues, 2);
+    }
+
+    #[test]
+    fn device_overrides_command_line() {
+        let devices = expand(&[
+            "--mtu",
+            "1300",
+            "--queues",
+            "2",
+            "--device",
+            "name=tap1,mtu=1400",
+        ]);
+
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "tap1");
+        assert_eq!(devices[0].mtu, Some(1400));
+        assert_eq!(devices[0].queues, 2);
+    }
+
+    #[test]
+    fn device_overrides_command_line_over_config() {
+        let path = config_file("device", "[[device]]\nname = \"tap1\"\nmtu = 1400\n");
+        let devices = expand(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--mtu",
+            "1300",
+            "--device",
+            "name=tap2,mtu=1200",
+        ]);
+        fs::remove_file(&path).unwrap();
+
+        let mtus: Vec<_> = devices.iter().map(|d| (d.name.as_str(), d.mtu)).collect();
+        assert_eq!(mtus, [("tap1", Some(1300)), ("tap2", Some(1200))]);
+    }
+}
@@ -0,0 +1,256 @@
+//! 设备定义的 TOML 配置文件。
+//!
+//! 每个 `[[device]]` 表描述一个设备，字段与 `create` 子命令的同名选项 (连字符换成下划线) 一致:
+//!
+//! ```toml
+//! [[device]]
+//! name = "tap1"
+//! mac = "52:54:00:12:34:56"
+//! mtu = 1500
+//! ipv4 = ["10.0.0.1/24"]
+//! ipv6 = ["fd00::1/64"]
+//! rotate_mac = "5m"
+//! ```
+//!
+//! 同样的字段也可以通过 `--device name=tap1,mac=...,mtu=1400` 在命令行上给出。
+
+use crate::addr::IpCidr;
+use crate::info::OutputFormat;
+use crate::layer::DeviceLayer;
+use crate::mac::{MacAddr, MacFormat, MacPolicy};
+use crate::oui::MacPrefix;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashSet;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 配置文件的内容。
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default, rename = "device")]
+    pub devices: Vec<DeviceConfig>,
+}
+
+/// 配置文件中的一个设备。除 `name` 外的字段都可以省略，省略时使用命令行选项的默认值。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    pub layer: Option<DeviceLayer>,
+    pub queues: Option<u16>,
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub stats_interval: Option<Duration>,
+    pub mtu: Option<u16>,
+    pub jumbo: Option<bool>,
+    pub mac: Option<MacAddr>,
+    pub mac_seed: Option<String>,
+    pub mac_prefix: Option<MacPrefix>,
+    pub mac_state: Option<PathBuf>,
+    pub mac_policy: Option<MacPolicy>,
+    pub mac_fix: Option<bool>,
+    pub mac_format: Option<MacFormat>,
+    pub output: Option<OutputFormat>,
+    #[serde(default)]
+    pub ipv4: Vec<IpCidr>,
+    #[serde(default)]
+    pub ipv6: Vec<IpCidr>,
+    pub assign_link_local: Option<bool>,
+    #[serde(default)]
+    pub respond_ipv4: Vec<Ipv4Addr>,
+    #[serde(default)]
+    pub respond_ipv6: Vec<Ipv6Addr>,
+    pub control: Option<PathBuf>,
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub rotate_mac: Option<Duration>,
+    pub mac_history: Option<PathBuf>,
+    pub capture: Option<PathBuf>,
+    pub dump: Option<bool>,
+    pub hex: Option<bool>,
+    pub persist: Option<bool>,
+    /// 用户名或数字 UID，以字符串表示
+    pub owner: Option<String>,
+    /// 组名或数字 GID，以字符串表示
+    pub group: Option<String>,
+}
+
+/// 以 humantime 格式 (例如 `30s`、`5m`) 解析定时任务的间隔，拒绝零。
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    match humantime::parse_duration(s) {
+        Ok(Duration::ZERO) => Err(format!("间隔 '{}' 必须大于零", s)),
+        Ok(interval) => Ok(interval),
+        Err(e) => Err(format!("无效的时长 '{}': {}", s, e)),
+    }
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_interval(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+impl ConfigFile {
+    /// 读取并解析配置文件，检查设备名是否重复。
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("读取配置文件 '{}' 失败", path.display()))?;
+        let config: ConfigFile = toml::from_str(&content)
+            .with_context(|| format!("解析配置文件 '{}' 失败", path.display()))?;
+
+        if config.devices.is_empty() {
+            bail!("配置文件 '{}' 中没有定义任何设备", path.display());
+        }
+        let mut names = HashSet::new();
+        for device in &config.devices {
+            if !names.insert(device.name.as_str()) {
+                bail!("配置文件中设备 '{}' 被重复定义", device.name);
+            }
+        }
+        Ok(config)
+    }
+
+    /// 按名称查找设备。
+    pub fn get(&self, name: &str) -> Result<&DeviceConfig> {
+        self.devices
+            .iter()
+            .find(|d| d.name == name)
+            .with_context(|| format!("配置文件中没有名为 '{}' 的设备", name))
+    }
+}
+
+/// `--device` 中取整数值的键。
+const INTEGER_KEYS: &[&str] = &["queues", "mtu"];
+
+/// `--device` 中取布尔值的键，只写键名时视为 `true`。
+const BOOL_KEYS: &[&str] = &[
+    "jumbo",
+    "mac_fix",
+    "assign_link_local",
+    "dump",
+    "hex",
+    "persist",
+];
+
+/// `--device` 中可以重复、组成列表的键。
+const LIST_KEYS: &[&str] = &["ipv4", "ipv6", "respond_ipv4", "respond_ipv6"];
+
+impl FromStr for DeviceConfig {
+    type Err = String;
+
+    /// 解析 `--device` 的 `name=tap1,mac=52:54:00:12:34:56,mtu=1400,ipv4=10.0.0.1/24` 形式。
+    ///
+    /// 键与配置文件中的字段相同，也可以写成连字符形式 (`mac-seed`)。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut table = toml::Table::new();
+        for item in s.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = match item.split_once('=') {
+                Some((key, value)) => (key.trim().replace('-', "_"), Some(value.trim())),
+                None => (item.trim().replace('-', "_"), None),
+            };
+            let value = match (key.as_str(), value) {
+                (k, None) if BOOL_KEYS.contains(&k) => toml::Value::Boolean(true),
+                (_, None) => return Err(format!("'{}' 缺少值，应写成 {}=<值>", key, key)),
+                (k, Some(v)) if BOOL_KEYS.contains(&k) => toml::Value::Boolean(
+                    v.parse()
+                        .map_err(|_| format!("'{}' 的值应为 true 或 false", key))?,
+                ),
+                (k, Some(v)) if INTEGER_KEYS.contains(&k) => toml::Value::Integer(
+                    v.parse()
+                        .map_err(|_| format!("'{}' 的值 '{}' 不是整数", key, v))?,
+                ),
+                (_, Some(v)) => toml::Value::String(v.to_string()),
+            };
+            if LIST_KEYS.contains(&key.as_str()) {
+                table
+                    .entry(key)
+                    .or_insert_with(|| toml::Value::Array(Vec::new()))
+                    .as_array_mut()
+                    .expect("列表键总是数组")
+                    .push(value);
+            } else if table.insert(key.clone(), value).is_some() {
+                return Err(format!("'{}' 被重复指定", key));
+            }
+        }
+        table
+            .try_into()
+            .map_err(|e: toml::de::Error| e.message().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(s: &str) -> DeviceConfig {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn bare_keys_are_true_booleans() {
+        let config = device("name=tap1,dump,hex=false,mac-fix");
+        assert_eq!(config.dump, Some(true));
+        assert_eq!(config.hex, Some(false));
+        assert_eq!(config.mac_fix, Some(true));
+        assert!("name=tap1,mtu".parse::<DeviceConfig>().is_err());
+        assert!("name=tap1,dump=yes".parse::<DeviceConfig>().is_err());
+    }
+
+    #[test]
+    fn dashes_become_underscores() {
+        let config = device("name=tap1,mac-seed=lab,assign-link-local,rotate-mac=5m");
+        assert_eq!(config.mac_seed.as_deref(), Some("lab"));
+        assert_eq!(config.assign_link_local, Some(true));
+        assert_eq!(config.rotate_mac, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn repeated_list_keys_accumulate() {
+        let config = device("name=tap1,ipv4=10.0.0.1/24,ipv4=10.0.1.1/24,respond-ipv6=fd00::2");
+        assert_eq!(
+            config.ipv4,
+            vec![
+                "10.0.0.1/24".parse::<IpCidr>().unwrap(),
+                "10.0.1.1/24".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            config.respond_ipv6,
+            vec!["fd00::2".parse::<Ipv6Addr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_scalar_keys() {
+        assert!("name=tap1,mtu=1400,mtu=1500"
+            .parse::<DeviceConfig>()
+            .is_err());
+        assert!("name=tap1,mac-seed=a,mac_seed=b"
+            .parse::<DeviceConfig>()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_bad_values_and_unknown_keys() {
+        assert!("name=tap1,mtu=big".parse::<DeviceConfig>().is_err());
+        assert!("name=tap1,queues=-1".parse::<DeviceConfig>().is_err());
+        assert!("name=tap1,colour=red".parse::<DeviceConfig>().is_err());
+        assert!("mtu=1400".parse::<DeviceConfig>().is_err());
+    }
+
+    #[test]
+    fn rejects_zero_intervals() {
+        assert_eq!(parse_interval("30s"), Ok(Duration::from_secs(30)));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("0s").is_err());
+        assert!("name=tap1,stats-interval=0s"
+            .parse::<DeviceConfig>()
+            .is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! 运行时控制套接字。
+//!
+//! 监听一个 Unix 套接字，每行一条命令，每条命令回复一行 `ok ...` 或 `error ...`：
+//!
+//! - `mac`: 查询当前MAC地址
+//! - `mac <地址>`: 更换为指定地址
+//! - `mac random`: 更换为新的随机本地管理地址
+//!
+//! 例如: `echo "mac random" | socat - UNIX-CONNECT:/run/tap0.sock`
+
+use crate::change::change_mac;
+use crate::logging;
+use crate::mac::{check_mac, generate_random_mac, MacAddr, MacPolicy};
+use crate::netlink::Netlink;
+use crate::sys;
+use anyhow::{anyhow, bail, Context, Result};
+use log::{info, warn};
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/// 正在监听的控制套接字，drop 时删除套接字文件。
+pub struct ControlSocket {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl ControlSocket {
+    pub fn bind(path: &Path) -> Result<Self> {
+        // 清理上次异常退出留下的套接字文件，但不删除其他类型的文件，也不抢占仍在监听的套接字
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                match std::os::unix::net::UnixStream::connect(path) {
+                    Ok(_) => bail!("控制套接字 '{}' 正被另一个实例使用", path.display()),
+                    Err(e) if e.kind() == ErrorKind::ConnectionRefused => {}
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("检查旧的控制套接字 '{}' 失败", path.display())
+                        })
+                    }
+                }
+                fs::remove_file(path)
+                    .with_context(|| format!("删除旧的控制套接字 '{}' 失败", path.display()))?
+            }
+            Ok(_) => bail!("'{}' 已存在且不是套接字，拒绝覆盖", path.display()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("检查控制套接字路径 '{}' 失败", path.display()))
+            }
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("监听控制套接字 '{}' 失败", path.display()))?;
+        info!("控制套接字已在 '{}' 上监听", path.display());
+        Ok(ControlSocket {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// 处理接口 `name` 的控制连接，直到出错。
+    pub async fn serve(&self, netlink: Netlink, name: String) -> Result<()> {
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let netlink = netlink.clone();
+            let name = name.clone();
+            tokio::spawn(logging::inherit(async move {
+                if let Err(e) = handle_connection(stream, &netlink, &name).await {
+                    warn!("控制连接出错: {:#}", e);
+                }
+            }));
+        }
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+async fn handle_connection(stream: UnixStream, netlink: &Netlink, name: &str) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let reply = match execute(line, netlink, name).await {
+            Ok(reply) => format!("ok {}\n", reply),
+            Err(e) => format!("error {:#}\n", e),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+async fn execute(line: &str, netlink: &Netlink, name: &str) -> Result<String> {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("mac"), None, None) => Ok(sys::hardware_address(name)?.to_string()),
+        (Some("mac"), Some(arg), None) => {
+            let new_mac = if arg == "random" {
+                generate_random_mac()
+            } else {
+                let mac: MacAddr = arg.parse().map_err(|e: String| anyhow!(e))?;
+                check_mac(mac, MacPolicy::Strict, false)?
+            };
+            let old_mac = change_mac(netlink, name, new_mac).await?;
+            info!(
+                "控制套接字: 设备 '{}' 的MAC地址已从 {} 更换为 {}",
+                name,
+                old_mac.map(|m| m.to_string()).unwrap_or_default(),
+                new_mac
+            );
+            Ok(new_mac.to_string())
+        }
+        _ => Err(anyhow!(
+            "未知命令 '{}'。可用命令: mac [<地址>|random]",
+            line
+        )),
+    }
+}
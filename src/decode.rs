@@ -0,0 +1,524 @@
+//! 以太网帧及其中 ARP、IPv4、IPv6 包的解析，以及单行摘要。
+
+use crate::layer::DeviceLayer;
+use crate::mac::MacAddr;
+use crate::packet::{
+    ETHERNET_HEADER_LEN, ETHERTYPE_ARP, ETHERTYPE_IPV4, ETHERTYPE_IPV6, ETHERTYPE_VLAN,
+    ICMPV6_NEIGHBOR_ADVERTISEMENT, ICMPV6_NEIGHBOR_SOLICITATION, IPPROTO_ICMPV6,
+};
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// 802.1ad (QinQ) 外层标签。
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+const IPPROTO_ICMP: u8 = 1;
+const IPPROTO_IGMP: u8 = 2;
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+const IPPROTO_SCTP: u8 = 132;
+
+/// 解析后的以太网帧。
+#[derive(Clone, Debug)]
+pub struct EthernetFrame<'a> {
+    pub dst: MacAddr,
+    pub src: MacAddr,
+    /// 由外到内的 VLAN ID
+    pub vlans: Vec<u16>,
+    pub ethertype: u16,
+    pub payload: &'a [u8],
+}
+
+/// 解析以太网首部，跳过所有 802.1Q / 802.1ad 标签。
+pub fn parse_ethernet(frame: &[u8]) -> Option<EthernetFrame<'_>> {
+    if frame.len() < ETHERNET_HEADER_LEN {
+        return None;
+    }
+    let mut dst = [0u8; 6];
+    let mut src = [0u8; 6];
+    dst.copy_from_slice(&frame[..6]);
+    src.copy_from_slice(&frame[6..12]);
+    let mut ethertype = u16::from_be_bytes([frame[12], frame[13]]);
+    let mut offset = ETHERNET_HEADER_LEN;
+    let mut vlans = Vec::new();
+    while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+        let tag = frame.get(offset..offset + 4)?;
+        vlans.push(u16::from_be_bytes([tag[0], tag[1]]) & 0x0fff);
+        ethertype = u16::from_be_bytes([tag[2], tag[3]]);
+        offset += 4;
+    }
+    Some(EthernetFrame {
+        dst: MacAddr::new(dst),
+        src: MacAddr::new(src),
+        vlans,
+        ethertype,
+        payload: &frame[offset..],
+    })
+}
+
+/// 以太网上的 IPv4 ARP 包。
+#[derive(Clone, Copy, Debug)]
+pub struct ArpPacket {
+    pub op: u16,
+    pub sender_mac: MacAddr,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: MacAddr,
+    pub target_ip: Ipv4Addr,
+}
+
+/// 解析 ARP 包，只接受以太网 / IPv4 的组合。
+pub fn parse_arp(payload: &[u8]) -> Option<ArpPacket> {
+    let p = payload.get(..28)?;
+    // 硬件类型 1 (以太网)、协议类型 IPv4、地址长度 6 和 4
+    if p[..6] != [0, 1, 0x08, 0x00, 6, 4] {
+        return None;
+    }
+    let mac = |o: usize| MacAddr::new([p[o], p[o + 1], p[o + 2], p[o + 3], p[o + 4], p[o + 5]]);
+    let ip = |o: usize| Ipv4Addr::new(p[o], p[o + 1], p[o + 2], p[o + 3]);
+    Some(ArpPacket {
+        op: u16::from_be_bytes([p[6], p[7]]),
+        sender_mac: mac(8),
+        sender_ip: ip(14),
+        target_mac: mac(18),
+        target_ip: ip(24),
+    })
+}
+
+/// ICMPv6 邻居请求 (NS)。
+#[derive(Clone, Copy, Debug)]
+pub struct NeighborSolicitation {
+    pub target: Ipv6Addr,
+    /// 源链路层地址选项，DAD 探测中没有该选项
+    pub source_mac: Option<MacAddr>,
+}
+
+/// 解析 ICMPv6 报文中的邻居请求。
+pub fn parse_neighbor_solicitation(icmp: &[u8]) -> Option<NeighborSolicitation> {
+    if icmp.len() < 24 || icmp[0] != ICMPV6_NEIGHBOR_SOLICITATION || icmp[1] != 0 {
+        return None;
+    }
+    let mut target = [0u8; 16];
+    target.copy_from_slice(&icmp[8..24]);
+
+    let mut source_mac = None;
+    let mut options = &icmp[24..];
+    while options.len() >= 8 {
+        let len = usize::from(options[1]) * 8;
+        if len == 0 || len > options.len() {
+            break;
+        }
+        // 选项 1: 源链路层地址
+        if options[0] == 1 && len == 8 {
+            let o = &options[2..8];
+            source_mac = Some(MacAddr::new([o[0], o[1], o[2], o[3], o[4], o[5]]));
+        }
+        options = &options[len..];
+    }
+    Some(NeighborSolicitation {
+        target: Ipv6Addr::from(target),
+        source_mac,
+    })
+}
+
+/// IPv4 或 IPv6 包，`payload` 为上层协议的数据。
+#[derive(Clone, Debug)]
+pub struct IpPacket<'a> {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    /// 上层协议号 (IPv6 为跳过扩展首部后的 Next Header)
+    pub protocol: u8,
+    /// TTL 或 Hop Limit
+    pub hop_limit: u8,
+    /// 非首个分片时为真，此时 `payload` 中没有上层协议首部
+    pub fragment: bool,
+    pub payload: &'a [u8],
+}
+
+/// 解析 IPv4 首部。
+pub fn parse_ipv4(packet: &[u8]) -> Option<IpPacket<'_>> {
+    let header = packet.get(..20)?;
+    if header[0] >> 4 != 4 {
+        return None;
+    }
+    let header_len = usize::from(header[0] & 0x0f) * 4;
+    if header_len < 20 || header_len > packet.len() {
+        return None;
+    }
+    let total_len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    let end = total_len.min(packet.len()).max(header_len);
+    let fragment_offset = u16::from_be_bytes([header[6], header[7]]) & 0x1fff;
+    Some(IpPacket {
+        src: IpAddr::V4(Ipv4Addr::new(
+            header[12], header[13], header[14], header[15],
+        )),
+        dst: IpAddr::V4(Ipv4Addr::new(
+            header[16], header[17], header[18], header[19],
+        )),
+        protocol: header[9],
+        hop_limit: header[8],
+        fragment: fragment_offset != 0,
+        payload: &packet[header_len..end],
+    })
+}
+
+/// 解析 IPv6 首部，跳过逐跳、路由、分片和目的选项扩展首部。
+pub fn parse_ipv6(packet: &[u8]) -> Option<IpPacket<'_>> {
+    let header = packet.get(..40)?;
+    if header[0] >> 4 != 6 {
+        return None;
+    }
+    let payload_len = usize::from(u16::from_be_bytes([header[4], header[5]]));
+    let end = (40 + payload_len).min(packet.len());
+    let mut src = [0u8; 16];
+    let mut dst = [0u8; 16];
+    src.copy_from_slice(&header[8..24]);
+    dst.copy_from_slice(&header[24..40]);
+
+    let mut next = header[6];
+    let mut offset = 40;
+    let mut fragment = false;
+    loop {
+        match next {
+            // 逐跳选项、路由、目的选项
+            0 | 43 | 60 => {
+                let ext = packet.get(offset..offset + 2)?;
+                next = ext[0];
+                offset += (usize::from(ext[1]) + 1) * 8;
+            }
+            // 分片首部
+            44 => {
+                let ext = packet.get(offset..offset + 8)?;
+                next = ext[0];
+                fragment = u16::from_be_bytes([ext[2], ext[3]]) >> 3 != 0;
+                offset += 8;
+            }
+            _ => break,
+        }
+    }
+    Some(IpPacket {
+        src: IpAddr::V6(Ipv6Addr::from(src)),
+        dst: IpAddr::V6(Ipv6Addr::from(dst)),
+        protocol: next,
+        hop_limit: header[7],
+        fragment,
+        payload: packet.get(offset..end)?,
+    })
+}
+
+/// 一帧的单行摘要。`layer` 为 L3 时 `frame` 是不带以太网首部的裸 IP 包。
+pub fn summary(frame: &[u8], layer: DeviceLayer) -> String {
+    let mut out = String::new();
+    match layer {
+        DeviceLayer::L2 => match parse_ethernet(frame) {
+            Some(eth) => {
+                let _ = write!(out, "{} > {}", eth.src, eth.dst);
+                for vlan in &eth.vlans {
+                    let _ = write!(out, " vlan {}", vlan);
+                }
+                out.push_str(", ");
+                ethertype_summary(&mut out, eth.ethertype, eth.payload);
+            }
+            None => out.push_str("截断的以太网帧"),
+        },
+        DeviceLayer::L3 => match frame.first().map(|b| b >> 4) {
+            Some(4) => ethertype_summary(&mut out, ETHERTYPE_IPV4, frame),
+            Some(6) => ethertype_summary(&mut out, ETHERTYPE_IPV6, frame),
+            _ => out.push_str("未知的 IP 版本"),
+        },
+    }
+    let _ = write!(out, ", {} 字节", frame.len());
+    out
+}
+
+fn ethertype_summary(out: &mut String, ethertype: u16, payload: &[u8]) {
+    match ethertype {
+        ETHERTYPE_ARP => match parse_arp(payload) {
+            Some(arp) => arp_summary(out, &arp),
+            None => out.push_str("ARP (无法解析)"),
+        },
+        ETHERTYPE_IPV4 => match parse_ipv4(payload) {
+            Some(ip) => ip_summary(out, "IPv4", &ip),
+            None => out.push_str("IPv4 (截断)"),
+        },
+        ETHERTYPE_IPV6 => match parse_ipv6(payload) {
+            Some(ip) => ip_summary(out, "IPv6", &ip),
+            None => out.push_str("IPv6 (截断)"),
+        },
+        other => {
+            let _ = write!(out, "ethertype 0x{:04x}", other);
+        }
+    }
+}
+
+fn arp_summary(out: &mut String, arp: &ArpPacket) {
+    let _ = match arp.op {
+        1 if arp.sender_ip == arp.target_ip => write!(out, "ARP announce {}", arp.sender_ip),
+        1 => write!(out, "ARP who-has {} tell {}", arp.target_ip, arp.sender_ip),
+        2 => write!(out, "ARP reply {} is-at {}", arp.sender_ip, arp.sender_mac),
+        op => write!(out, "ARP op {}", op),
+    };
+}
+
+/// `地址:端口` 形式，IPv6 地址加方括号。
+fn endpoint(addr: IpAddr, port: u16) -> String {
+    match addr {
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+    }
+}
+
+fn ip_summary(out: &mut String, version: &str, ip: &IpPacket) {
+    let name = match ip.protocol {
+        IPPROTO_TCP => Some("TCP"),
+        IPPROTO_UDP => Some("UDP"),
+        IPPROTO_SCTP => Some("SCTP"),
+        _ => None,
+    };
+    if let (Some(name), false, Some(ports)) = (name, ip.fragment, ip.payload.get(..4)) {
+        let src_port = u16::from_be_bytes([ports[0], ports[1]]);
+        let dst_port = u16::from_be_bytes([ports[2], ports[3]]);
+        let _ = write!(
+            out,
+            "{} {} > {} {}",
+            version,
+            endpoint(ip.src, src_port),
+            endpoint(ip.dst, dst_port),
+            name
+        );
+        return;
+    }
+
+    let _ = write!(out, "{} {} > {} ", version, ip.src, ip.dst);
+    if ip.fragment {
+        let _ = write!(out, "分片 (proto {})", ip.protocol);
+        return;
+    }
+    let icmp_type = ip.payload.first().copied();
+    let _ = match (ip.protocol, icmp_type) {
+        (IPPROTO_ICMP, Some(t)) => write!(out, "ICMP {}", icmp_name(t)),
+        (IPPROTO_ICMPV6, Some(t)) => {
+            let _ = write!(out, "ICMPv6 {}", icmpv6_name(t));
+            // NS / NA 的目标地址紧跟在 4 字节保留字段之后
+            if t == ICMPV6_NEIGHBOR_SOLICITATION || t == ICMPV6_NEIGHBOR_ADVERTISEMENT {
+                if let Some(target) = ip.payload.get(8..24) {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(target);
+                    let _ = write!(out, " {}", Ipv6Addr::from(octets));
+                }
+            }
+            Ok(())
+        }
+        (IPPROTO_IGMP, _) => write!(out, "IGMP"),
+        (IPPROTO_TCP, _) => write!(out, "TCP (截断)"),
+        (IPPROTO_UDP, _) => write!(out, "UDP (截断)"),
+        (protocol, _) => write!(out, "proto {}", protocol),
+    };
+}
+
+fn icmp_name(icmp_type: u8) -> String {
+    match icmp_type {
+        0 => "echo reply".to_string(),
+        3 => "destination unreachable".to_string(),
+        5 => "redirect".to_string(),
+        8 => "echo request".to_string(),
+        11 => "time exceeded".to_string(),
+        t => format!("type {}", t),
+    }
+}
+
+fn icmpv6_name(icmp_type: u8) -> String {
+    match icmp_type {
+        1 => "destination unreachable".to_string(),
+        2 => "packet too big".to_string(),
+        3 => "time exceeded".to_string(),
+        128 => "echo request".to_string(),
+        129 => "echo reply".to_string(),
+        133 => "router solicitation".to_string(),
+        134 => "router advertisement".to_string(),
+        ICMPV6_NEIGHBOR_SOLICITATION => "neighbor solicitation".to_string(),
+        ICMPV6_NEIGHBOR_ADVERTISEMENT => "neighbor advertisement".to_string(),
+        143 => "MLDv2 report".to_string(),
+        t => format!("type {}", t),
+    }
+}
+
+/// 每行 16 字节的十六进制转储，右侧附可打印字符。
+pub fn hexdump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let _ = write!(out, "  {:04x}  ", i * 16);
+        for j in 0..16 {
+            match chunk.get(j) {
+                Some(b) => {
+                    let _ = write!(out, "{:02x} ", b);
+                }
+                None => out.push_str("   "),
+            }
+            if j == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20 字节首部的 IPv4 UDP 包，载荷为端口 1234 > 53。
+    fn ipv4_udp() -> Vec<u8> {
+        let mut packet = vec![
+            0x45,
+            0,
+            0,
+            28,
+            0,
+            0,
+            0,
+            0,
+            64,
+            IPPROTO_UDP,
+            0,
+            0,
+            10,
+            0,
+            0,
+            1,
+            10,
+            0,
+            0,
+            2,
+        ];
+        packet.extend_from_slice(&[0x04, 0xd2, 0x00, 0x35, 0, 8, 0, 0]);
+        packet
+    }
+
+    /// 不带扩展首部的 IPv6 包，载荷为 `payload`。
+    fn ipv6(next_header: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x60, 0, 0, 0];
+        packet.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[next_header, 64]);
+        packet.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        packet.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn parses_ipv4() {
+        let packet = ipv4_udp();
+        let ip = parse_ipv4(&packet).unwrap();
+        assert_eq!(ip.src, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(ip.protocol, IPPROTO_UDP);
+        assert!(!ip.fragment);
+        assert_eq!(ip.payload.len(), 8);
+    }
+
+    #[test]
+    fn rejects_malformed_ipv4() {
+        let packet = ipv4_udp();
+        // 不足 20 字节
+        assert!(parse_ipv4(&packet[..19]).is_none());
+        // IHL 小于 5
+        let mut short_ihl = packet.clone();
+        short_ihl[0] = 0x44;
+        assert!(parse_ipv4(&short_ihl).is_none());
+        // IHL 声明的首部比实际字节长
+        let mut long_ihl = packet[..24].to_vec();
+        long_ihl[0] = 0x4f;
+        assert!(parse_ipv4(&long_ihl).is_none());
+        assert_eq!(summary(&long_ihl, DeviceLayer::L3), "IPv4 (截断), 24 字节");
+        // 版本不是 4
+        let mut version = packet.clone();
+        version[0] = 0x65;
+        assert!(parse_ipv4(&version).is_none());
+    }
+
+    #[test]
+    fn clamps_ipv4_total_length() {
+        let mut packet = ipv4_udp();
+        // 总长度大于实际字节数时截到实际长度
+        packet[2..4].copy_from_slice(&1000u16.to_be_bytes());
+        assert_eq!(parse_ipv4(&packet).unwrap().payload.len(), 8);
+        // 总长度小于首部长度时载荷为空
+        packet[2..4].copy_from_slice(&4u16.to_be_bytes());
+        assert!(parse_ipv4(&packet).unwrap().payload.is_empty());
+    }
+
+    #[test]
+    fn parses_ipv6_extension_headers() {
+        // 逐跳选项首部 (8 字节) 之后是 UDP
+        let mut payload = vec![IPPROTO_UDP, 0, 0, 0, 0, 0, 0, 0];
+        payload.extend_from_slice(&[0x02, 0x22, 0x02, 0x23, 0, 8, 0, 0]);
+        let packet = ipv6(0, &payload);
+        let ip = parse_ipv6(&packet).unwrap();
+        assert_eq!(ip.protocol, IPPROTO_UDP);
+        assert_eq!(ip.payload.len(), 8);
+    }
+
+    #[test]
+    fn rejects_malformed_ipv6() {
+        let packet = ipv6(IPPROTO_UDP, &[0; 8]);
+        assert!(parse_ipv6(&packet[..39]).is_none());
+        let mut version = packet.clone();
+        version[0] = 0x40;
+        assert!(parse_ipv6(&version).is_none());
+        // 扩展首部声明的长度超出包尾
+        let truncated = ipv6(0, &[IPPROTO_UDP, 4]);
+        assert!(parse_ipv6(&truncated).is_none());
+        // 分片首部被截断
+        let fragment = ipv6(44, &[IPPROTO_UDP, 0, 0]);
+        assert!(parse_ipv6(&fragment).is_none());
+    }
+
+    #[test]
+    fn parses_ipv6_payload_length_beyond_packet() {
+        let mut packet = ipv6(IPPROTO_UDP, &[0; 8]);
+        packet[4..6].copy_from_slice(&1000u16.to_be_bytes());
+        assert_eq!(parse_ipv6(&packet).unwrap().payload.len(), 8);
+    }
+
+    #[test]
+    fn parses_ethernet_vlan_tags() {
+        let mut frame = vec![0xff; 6];
+        frame.extend_from_slice(&[2, 0, 0, 0, 0, 1]);
+        frame.extend_from_slice(&[0x88, 0xa8, 0x00, 0x64, 0x81, 0x00, 0x20, 0x0a, 0x08, 0x06]);
+        frame.extend_from_slice(&[1, 2, 3]);
+        let eth = parse_ethernet(&frame).unwrap();
+        assert_eq!(eth.vlans, vec![100, 10]);
+        assert_eq!(eth.ethertype, ETHERTYPE_ARP);
+        assert_eq!(eth.payload, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_truncated_ethernet() {
+        assert!(parse_ethernet(&[0; 13]).is_none());
+        // VLAN 标签被截断
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x81, 0x00, 0x00]);
+        assert!(parse_ethernet(&frame).is_none());
+        assert_eq!(summary(&frame, DeviceLayer::L2), "截断的以太网帧, 15 字节");
+    }
+
+    #[test]
+    fn summary_never_panics_on_short_input() {
+        let packet = ipv4_udp();
+        for len in 0..packet.len() {
+            summary(&packet[..len], DeviceLayer::L3);
+        }
+        let packet = ipv6(IPPROTO_ICMPV6, &[ICMPV6_NEIGHBOR_SOLICITATION, 0, 0, 0]);
+        for len in 0..packet.len() {
+            summary(&packet[..len], DeviceLayer::L3);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! 在日志中逐帧打印设备上读到的帧。
+
+use crate::decode::{hexdump, parse_ethernet, summary};
+use crate::layer::DeviceLayer;
+use crate::mac::MacAddr;
+use crate::queue::FrameHandler;
+use anyhow::Result;
+use log::info;
+use std::io::{self, IsTerminal};
+use tokio::sync::watch;
+
+/// 每帧打印一行摘要，可选附加十六进制转储。
+///
+/// 源 MAC 等于设备自身地址 `mac` 的帧以 `*` 开头，标准错误是终端时还会加粗显示。
+/// L3 设备没有 MAC 地址，`mac` 为 `None`。
+pub struct Dump {
+    layer: DeviceLayer,
+    hex: bool,
+    bold: bool,
+    mac: Option<watch::Receiver<MacAddr>>,
+}
+
+impl Dump {
+    pub fn new(layer: DeviceLayer, hex: bool, mac: Option<watch::Receiver<MacAddr>>) -> Self {
+        Dump {
+            layer,
+            hex,
+            bold: io::stderr().is_terminal(),
+            mac,
+        }
+    }
+
+    /// 帧的源 MAC 是否为设备自身的地址。
+    fn is_own(&self, frame: &[u8]) -> bool {
+        match (&self.mac, parse_ethernet(frame)) {
+            (Some(mac), Some(eth)) => eth.src == *mac.borrow(),
+            _ => false,
+        }
+    }
+}
+
+impl FrameHandler for Dump {
+    fn handle(&self, frame: &[u8]) -> Result<Option<Vec<u8>>> {
+        let line = summary(frame, self.layer);
+        let line = match (self.is_own(frame), self.bold) {
+            (true, true) => format!("\x1b[1m* {}\x1b[0m", line),
+            (true, false) => format!("* {}", line),
+            (false, _) => format!("  {}", line),
+        };
+        if self.hex {
+            info!("{}\n{}", line, hexdump(frame).trim_end());
+        } else {
+            info!("{}", line);
+        }
+        Ok(None)
+    }
+}
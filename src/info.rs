@@ -0,0 +1,267 @@
+//! 设备信息的收集与展示。
+
+use crate::layer::DeviceLayer;
+use crate::mac::{MacAddr, MacFormat, MacSource};
+use crate::netlink::{AddressInfo, LinkInfo, Netlink};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv6Addr};
+
+/// 设备信息的输出格式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 与 `ip addr show` 相同的布局
+    #[default]
+    Ip,
+    /// 与 `ip -brief addr show` 相同的单行布局
+    Brief,
+    /// 机器可读的 JSON 文档
+    Json,
+}
+
+/// 创建设备时请求的MAC地址及其来源。
+#[derive(Clone, Copy, Debug)]
+pub struct RequestedMac {
+    pub mac: MacAddr,
+    pub source: MacSource,
+}
+
+/// 一个接口的链路信息及其地址。
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub link: LinkInfo,
+    pub addresses: Vec<AddressInfo>,
+    /// 由本工具创建的设备才有此项
+    pub requested: Option<RequestedMac>,
+}
+
+impl DeviceInfo {
+    pub async fn query(netlink: &Netlink, name: &str) -> Result<Self> {
+        let link = netlink.link_by_name(name).await?;
+        let addresses = netlink.addresses(link.index).await?;
+        Ok(DeviceInfo {
+            link,
+            addresses,
+            requested: None,
+        })
+    }
+
+    pub fn render(&self, output: OutputFormat, mac_format: MacFormat) -> String {
+        match output {
+            OutputFormat::Ip => self.render_ip(mac_format),
+            OutputFormat::Brief => self.render_brief(mac_format),
+            OutputFormat::Json => self.render_json(),
+        }
+    }
+
+    /// 由链路类型推断的层: 以太网为 L2，无链路层为 L3。
+    pub fn layer(&self) -> Option<DeviceLayer> {
+        match self.link.link_type {
+            libc::ARPHRD_ETHER => Some(DeviceLayer::L2),
+            libc::ARPHRD_NONE => Some(DeviceLayer::L3),
+            _ => None,
+        }
+    }
+
+    fn render_json(&self) -> String {
+        // 这些结构只包含字符串和数字，序列化不会失败
+        serde_json::to_string_pretty(&self.json_report()).expect("序列化设备信息失败") + "\n"
+    }
+
+    fn json_report(&self) -> JsonReport<'_> {
+        let link = &self.link;
+        JsonReport {
+            name: &link.name,
+            ifindex: link.index,
+            layer: self.layer(),
+            mtu: link.mtu,
+            flags: flag_names(link.flags),
+            oper_state: link.oper_state.as_deref(),
+            mac: JsonMac {
+                requested: self.requested.map(|r| r.mac),
+                actual: link.mac,
+                matches: self.requested.map(|r| Some(r.mac) == link.mac),
+                source: self.requested.map(|r| r.source),
+                generated: self.requested.map(|r| r.source.is_generated()),
+                link_local: self.requested.map(|r| r.mac.ipv6_link_local()),
+                link_local_present: self.requested.map(|r| {
+                    let expected = IpAddr::V6(r.mac.ipv6_link_local());
+                    self.addresses.iter().any(|a| a.address == expected)
+                }),
+            },
+            addresses: self
+                .addresses
+                .iter()
+                .map(|a| JsonAddress {
+                    family: match a.address {
+                        IpAddr::V4(_) => "inet",
+                        IpAddr::V6(_) => "inet6",
+                    },
+                    address: a.address,
+                    prefix_len: a.prefix_len,
+                    scope: scope_name(a.scope),
+                    broadcast: a.broadcast,
+                })
+                .collect(),
+        }
+    }
+
+    /// 把多个设备的 JSON 文档合成一个数组，让同时创建多个设备时 stdout 仍是单个 JSON 文档。
+    pub fn render_json_array(devices: &[DeviceInfo]) -> String {
+        let reports: Vec<JsonReport> = devices.iter().map(DeviceInfo::json_report).collect();
+        serde_json::to_string_pretty(&reports).expect("序列化设备信息失败") + "\n"
+    }
+
+    fn render_ip(&self, mac_format: MacFormat) -> String {
+        let link = &self.link;
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{}: {}: <{}>",
+            link.index,
+            link.name,
+            flag_names(link.flags).join(",")
+        );
+        if let Some(mtu) = link.mtu {
+            let _ = write!(out, " mtu {}", mtu);
+        }
+        if let Some(qdisc) = &link.qdisc {
+            let _ = write!(out, " qdisc {}", qdisc);
+        }
+        if let Some(state) = &link.oper_state {
+            let _ = write!(out, " state {}", state);
+        }
+        if let Some(qlen) = link.tx_queue_len {
+            let _ = write!(out, " qlen {}", qlen);
+        }
+        out.push('\n');
+
+        let _ = write!(out, "    link/{}", link_type_name(link.link_type));
+        if let Some(mac) = link.mac {
+            let _ = write!(out, " {}", mac.display(mac_format));
+        }
+        if let Some(brd) = link.broadcast {
+            let _ = write!(out, " brd {}", brd.display(mac_format));
+        }
+        out.push('\n');
+
+        for addr in &self.addresses {
+            let family = match addr.address {
+                IpAddr::V4(_) => "inet",
+                IpAddr::V6(_) => "inet6",
+            };
+            let _ = write!(out, "    {} {}/{}", family, addr.address, addr.prefix_len);
+            if let Some(brd) = addr.broadcast {
+                let _ = write!(out, " brd {}", brd);
+            }
+            let _ = write!(out, " scope {}", scope_name(addr.scope));
+            if let Some(label) = &addr.label {
+                let _ = write!(out, " {}", label);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_brief(&self, mac_format: MacFormat) -> String {
+        let link = &self.link;
+        let addresses: Vec<String> = self
+            .addresses
+            .iter()
+            .map(|a| format!("{}/{}", a.address, a.prefix_len))
+            .collect();
+        format!(
+            "{:<16} {:<14} {:<17} {}\n",
+            link.name,
+            link.oper_state.as_deref().unwrap_or("UNKNOWN"),
+            link.mac.unwrap_or(MacAddr::ZERO).display(mac_format),
+            addresses.join(" ")
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    name: &'a str,
+    ifindex: u32,
+    layer: Option<DeviceLayer>,
+    mtu: Option<u32>,
+    flags: Vec<&'static str>,
+    oper_state: Option<&'a str>,
+    mac: JsonMac,
+    addresses: Vec<JsonAddress>,
+}
+
+#[derive(Serialize)]
+struct JsonMac {
+    requested: Option<MacAddr>,
+    actual: Option<MacAddr>,
+    matches: Option<bool>,
+    source: Option<MacSource>,
+    generated: Option<bool>,
+    /// 由请求的MAC地址派生的 EUI-64 链路本地地址
+    link_local: Option<Ipv6Addr>,
+    /// 该链路本地地址是否出现在接口上
+    link_local_present: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct JsonAddress {
+    family: &'static str,
+    address: IpAddr,
+    prefix_len: u8,
+    scope: String,
+    broadcast: Option<IpAddr>,
+}
+
+/// 按 `ip` 的顺序列出置位的 IFF_* 标志名。
+pub fn flag_names(flags: u32) -> Vec<&'static str> {
+    const FLAGS: &[(i32, &str)] = &[
+        (libc::IFF_LOOPBACK, "LOOPBACK"),
+        (libc::IFF_BROADCAST, "BROADCAST"),
+        (libc::IFF_POINTOPOINT, "POINTOPOINT"),
+        (libc::IFF_MULTICAST, "MULTICAST"),
+        (libc::IFF_NOARP, "NOARP"),
+        (libc::IFF_ALLMULTI, "ALLMULTI"),
+        (libc::IFF_PROMISC, "PROMISC"),
+        (libc::IFF_MASTER, "MASTER"),
+        (libc::IFF_SLAVE, "SLAVE"),
+        (libc::IFF_UP, "UP"),
+        (libc::IFF_LOWER_UP, "LOWER_UP"),
+        (libc::IFF_DORMANT, "DORMANT"),
+    ];
+    let mut names = Vec::new();
+    let up = flags & libc::IFF_UP as u32 != 0;
+    let lower_up = flags & libc::IFF_LOWER_UP as u32 != 0;
+    if up && !lower_up {
+        names.push("NO-CARRIER");
+    }
+    for (flag, name) in FLAGS {
+        if flags & *flag as u32 != 0 {
+            names.push(*name);
+        }
+    }
+    names
+}
+
+fn link_type_name(link_type: u16) -> String {
+    match link_type {
+        libc::ARPHRD_ETHER => "ether".to_string(),
+        libc::ARPHRD_NONE => "none".to_string(),
+        libc::ARPHRD_LOOPBACK => "loopback".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn scope_name(scope: u8) -> String {
+    match scope {
+        libc::RT_SCOPE_UNIVERSE => "global".to_string(),
+        libc::RT_SCOPE_SITE => "site".to_string(),
+        libc::RT_SCOPE_LINK => "link".to_string(),
+        libc::RT_SCOPE_HOST => "host".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,44 @@
+//! 设备工作在哪一层。
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 设备的层：L2 为 TAP (以太网帧)，L3 为 TUN (裸 IP 包)。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceLayer {
+    /// TAP 设备，收发以太网帧
+    #[default]
+    L2,
+    /// TUN 设备，收发裸 IP 包，没有 MAC 地址
+    L3,
+}
+
+impl DeviceLayer {
+    /// 设备类型名称，用于日志。
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeviceLayer::L2 => "TAP",
+            DeviceLayer::L3 => "TUN",
+        }
+    }
+}
+
+impl fmt::Display for DeviceLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeviceLayer::L2 => "l2",
+            DeviceLayer::L3 => "l3",
+        })
+    }
+}
+
+impl From<DeviceLayer> for tun_rs::Layer {
+    fn from(layer: DeviceLayer) -> Self {
+        match layer {
+            DeviceLayer::L2 => tun_rs::Layer::L2,
+            DeviceLayer::L3 => tun_rs::Layer::L3,
+        }
+    }
+}
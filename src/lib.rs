@@ -0,0 +1,26 @@
+//! TAP 设备 MAC 地址测试工具的公共组件。
+
+pub mod addr;
+pub mod arp;
+pub mod capture;
+pub mod change;
+pub mod config;
+pub mod control;
+pub mod decode;
+pub mod dump;
+pub mod info;
+pub mod layer;
+pub mod list;
+pub mod logging;
+pub mod mac;
+pub mod mtu;
+pub mod ndp;
+pub mod netlink;
+pub mod oui;
+pub mod packet;
+pub mod queue;
+pub mod rotate;
+pub mod slaac;
+pub mod state;
+pub mod sys;
+pub mod tun;
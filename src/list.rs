@@ -0,0 +1,129 @@
+//! 列出系统中已有的 TUN/TAP 设备。
+
+use crate::layer::DeviceLayer;
+use crate::mac::{MacAddr, MacFormat};
+use crate::tun::{device_layer, group_name, tun_flags, user_name};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// 设备列表的输出格式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum)]
+pub enum ListFormat {
+    /// 每个设备一行的表格
+    #[default]
+    Table,
+    /// 机器可读的 JSON 数组
+    Json,
+}
+
+/// 从 sysfs 读出的一个 TUN/TAP 设备。
+#[derive(Clone, Debug, Serialize)]
+pub struct TunDevice {
+    pub name: String,
+    pub layer: DeviceLayer,
+    pub mac: Option<MacAddr>,
+    pub mtu: Option<u32>,
+    /// 未设置所有者时为 `None`
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+    pub persistent: bool,
+    pub multi_queue: bool,
+    pub tun_flags: u32,
+}
+
+/// 读取 `/sys/class/net/<name>/<attr>` 并去掉首尾空白，文件不存在或读取失败时返回 `None`。
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// 解析 sysfs 中的 `owner` / `group`，`-1` 表示未设置。
+fn parse_id(value: Option<String>) -> Option<u32> {
+    value?.parse::<i64>().ok()?.try_into().ok()
+}
+
+impl TunDevice {
+    /// 读取接口 `name` 的属性。接口不是 TUN/TAP 设备时返回 `None`。
+    pub fn read(name: &str) -> Option<Self> {
+        let flags = tun_flags(name)?;
+        let dir = Path::new(SYS_CLASS_NET).join(name);
+        let layer = device_layer(flags);
+        let mac = match layer {
+            DeviceLayer::L2 => read_attr(&dir, "address").and_then(|s| s.parse().ok()),
+            DeviceLayer::L3 => None,
+        };
+        Some(TunDevice {
+            name: name.to_string(),
+            layer,
+            mac,
+            mtu: read_attr(&dir, "mtu").and_then(|s| s.parse().ok()),
+            owner: parse_id(read_attr(&dir, "owner")),
+            group: parse_id(read_attr(&dir, "group")),
+            persistent: flags & libc::IFF_PERSIST as u32 != 0,
+            multi_queue: flags & libc::IFF_MULTI_QUEUE as u32 != 0,
+            tun_flags: flags,
+        })
+    }
+}
+
+/// 遍历 `/sys/class/net`，按名称顺序返回所有 TUN/TAP 设备。
+pub fn tun_devices() -> Result<Vec<TunDevice>> {
+    let entries =
+        fs::read_dir(SYS_CLASS_NET).with_context(|| format!("读取 {} 失败", SYS_CLASS_NET))?;
+    let mut devices = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("读取 {} 失败", SYS_CLASS_NET))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // 接口可能在遍历过程中被删除，此时直接跳过
+        if let Some(device) = TunDevice::read(&name) {
+            devices.push(device);
+        }
+    }
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(devices)
+}
+
+/// 按 `format` 渲染设备列表。
+pub fn render(devices: &[TunDevice], format: ListFormat, mac_format: MacFormat) -> String {
+    match format {
+        ListFormat::Table => render_table(devices, mac_format),
+        ListFormat::Json => {
+            // 这些结构只包含字符串和数字，序列化不会失败
+            serde_json::to_string_pretty(devices).expect("序列化设备列表失败") + "\n"
+        }
+    }
+}
+
+fn render_table(devices: &[TunDevice], mac_format: MacFormat) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<16} {:<4} {:<17} {:>5}  {:<10} {:<10} {:<7} MULTI_QUEUE",
+        "NAME", "TYPE", "MAC", "MTU", "OWNER", "GROUP", "PERSIST"
+    );
+    for device in devices {
+        let _ = writeln!(
+            out,
+            "{:<16} {:<4} {:<17} {:>5}  {:<10} {:<10} {:<7} {}",
+            device.name,
+            device.layer.kind(),
+            device
+                .mac
+                .map_or("-".to_string(), |mac| mac.display(mac_format).to_string()),
+            device.mtu.map_or("-".to_string(), |mtu| mtu.to_string()),
+            device.owner.map_or("-".to_string(), user_name),
+            device.group.map_or("-".to_string(), group_name),
+            yes_no(device.persistent),
+            yes_no(device.multi_queue)
+        );
+    }
+    out
+}
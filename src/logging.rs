@@ -0,0 +1,57 @@
+//! 日志初始化，以及同时运行多个设备时按设备区分的日志前缀。
+
+use log::{Log, Metadata, Record};
+use std::future::Future;
+
+tokio::task_local! {
+    /// 当前任务所属的设备名，为空时不加前缀。
+    static DEVICE: String;
+}
+
+/// 在 env_logger 的输出前加上当前任务所属设备名的包装。
+struct DeviceLogger(env_logger::Logger);
+
+impl Log for DeviceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        match DEVICE.try_with(|device| device.clone()) {
+            Ok(device) if !device.is_empty() => self.0.log(
+                &Record::builder()
+                    .args(format_args!("[{}] {}", device, record.args()))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            ),
+            _ => self.0.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+/// 初始化日志记录器，默认级别为 info，可通过 `RUST_LOG` 调整。
+pub fn init() {
+    let logger =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
+    let max_level = logger.filter();
+    log::set_boxed_logger(Box::new(DeviceLogger(logger))).expect("日志记录器只应初始化一次");
+    log::set_max_level(max_level);
+}
+
+/// 在 `fut` 中打印的日志都带上设备名 `device` 作为前缀。`device` 为空时不加前缀。
+pub async fn with_device<F: Future>(device: String, fut: F) -> F::Output {
+    DEVICE.scope(device, fut).await
+}
+
+/// 让 `fut` 沿用当前任务的设备名前缀，用于 `tokio::spawn` 出去的子任务。
+pub fn inherit<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    let device = DEVICE.try_with(|device| device.clone()).unwrap_or_default();
+    with_device(device, fut)
+}
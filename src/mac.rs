@@ -0,0 +1,548 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+/// 以太网 MAC 地址 (EUI-48)。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct MacAddr([u8; 6]);
+
+impl MacAddr {
+    /// 广播地址 ff:ff:ff:ff:ff:ff
+    pub const BROADCAST: MacAddr = MacAddr([0xff; 6]);
+    /// 全零地址 00:00:00:00:00:00
+    pub const ZERO: MacAddr = MacAddr([0; 6]);
+
+    pub const fn new(octets: [u8; 6]) -> Self {
+        MacAddr(octets)
+    }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// 设置 "Locally Administered" 位并清除 "Multicast" 位。
+    pub const fn to_local_unicast(self) -> Self {
+        let mut octets = self.0;
+        octets[0] |= 0x02; // 设置 "Locally Administered" 位
+        octets[0] &= 0xfe; // 清除 "Multicast" 位
+        MacAddr(octets)
+    }
+
+    /// 第一个字节的 I/G 位为 1 (包括广播地址)。
+    pub const fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    pub const fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// 第一个字节的 U/L 位为 1，即本地管理地址。
+    pub const fn is_local(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// U/L 位为 0，即由厂商分配的全球唯一地址。
+    pub const fn is_universal(&self) -> bool {
+        !self.is_local()
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// 修正 EUI-64 接口标识 (RFC 4291 附录 A)：在中间插入 ff:fe 并翻转 U/L 位。
+    pub const fn eui64(&self) -> [u8; 8] {
+        let [a, b, c, d, e, f] = self.0;
+        [a ^ 0x02, b, c, 0xff, 0xfe, d, e, f]
+    }
+
+    /// 由 EUI-64 接口标识组成的 fe80::/64 链路本地地址。
+    pub fn ipv6_link_local(&self) -> Ipv6Addr {
+        let mut octets = [0u8; 16];
+        octets[0] = 0xfe;
+        octets[1] = 0x80;
+        octets[8..].copy_from_slice(&self.eui64());
+        Ipv6Addr::from(octets)
+    }
+
+    /// 检查该地址能否作为网卡地址使用，返回发现的所有问题。
+    pub fn problems(&self) -> Vec<MacProblem> {
+        if self.is_zero() {
+            vec![MacProblem::Zero]
+        } else if self.is_broadcast() {
+            vec![MacProblem::Broadcast]
+        } else if self.is_multicast() {
+            vec![MacProblem::Multicast]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// 返回按 `format` 格式化的显示包装。
+    pub fn display(&self, format: MacFormat) -> FormattedMac {
+        FormattedMac { mac: *self, format }
+    }
+}
+
+/// 不能作为网卡地址使用的 MAC 地址类型。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacProblem {
+    /// 全零地址，内核会拒绝或视为未设置
+    Zero,
+    /// 广播地址 ff:ff:ff:ff:ff:ff
+    Broadcast,
+    /// 第一个字节的 I/G 位 (0x01) 被置位
+    Multicast,
+}
+
+impl fmt::Display for MacProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacProblem::Zero => f.write_str("全零地址 00:00:00:00:00:00 不是有效的网卡地址"),
+            MacProblem::Broadcast => f.write_str("广播地址 ff:ff:ff:ff:ff:ff 不能用作网卡地址"),
+            MacProblem::Multicast => f.write_str(
+                "第一个字节的最低位 (0x01, I/G 位) 被置位，这是一个组播地址，不能用作网卡地址",
+            ),
+        }
+    }
+}
+
+/// MAC 地址的来源。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MacSource {
+    /// 通过 --mac 提供
+    Supplied,
+    /// 随机生成
+    Random,
+    /// 由种子派生
+    Derived,
+    /// 从状态文件复用
+    State,
+}
+
+impl MacSource {
+    /// 地址是否由本工具生成 (而不是由用户提供)。
+    pub fn is_generated(&self) -> bool {
+        *self != MacSource::Supplied
+    }
+}
+
+/// 对不可用 MAC 地址的处理策略。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MacPolicy {
+    /// 拒绝不可用的地址并退出
+    #[default]
+    Strict,
+    /// 打印警告后继续使用
+    Warn,
+    /// 不做任何检查
+    Allow,
+}
+
+/// 按 `policy` 检查 `mac`，返回最终要使用的地址。
+///
+/// `fix` 为真时，不可用的地址会像 [`generate_random_mac`] 一样被置上本地管理位并清除组播位，
+/// 而不是报错。
+pub fn check_mac(mac: MacAddr, policy: MacPolicy, fix: bool) -> Result<MacAddr> {
+    if policy == MacPolicy::Allow {
+        return Ok(mac);
+    }
+    let problems = mac.problems();
+    if problems.is_empty() {
+        return Ok(mac);
+    }
+
+    if fix {
+        let fixed = mac.to_local_unicast();
+        for problem in &problems {
+            warn!("MAC地址 {}: {}", mac, problem);
+        }
+        warn!("已自动修正为 {}", fixed);
+        return Ok(fixed);
+    }
+
+    match policy {
+        MacPolicy::Strict => {
+            let reasons: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+            bail!(
+                "MAC地址 {} 不可用: {}。可使用 --mac-fix 自动修正，或使用 --mac-policy warn|allow 跳过检查",
+                mac,
+                reasons.join("; ")
+            );
+        }
+        _ => {
+            for problem in &problems {
+                warn!("MAC地址 {}: {}", mac, problem);
+            }
+            Ok(mac)
+        }
+    }
+}
+
+/// MAC 地址的分隔风格。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MacStyle {
+    /// `0a:0b:0c:0d:0e:0f`
+    #[default]
+    Colon,
+    /// `0a-0b-0c-0d-0e-0f`
+    Dash,
+    /// `0a0b.0c0d.0e0f` (Cisco)
+    Dotted,
+    /// `0a0b0c0d0e0f`
+    Bare,
+}
+
+/// MAC 地址的输出格式：分隔风格加大小写。
+///
+/// 字符串形式为 `<风格>[-upper|-lower]`，例如 `dotted`、`dash-upper`。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct MacFormat {
+    pub style: MacStyle,
+    pub uppercase: bool,
+}
+
+impl FromStr for MacFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let (style, uppercase) = match lower.rsplit_once('-') {
+            Some((style, "upper")) => (style, true),
+            Some((style, "lower")) => (style, false),
+            _ => (lower.as_str(), false),
+        };
+        let style = match style {
+            "colon" => MacStyle::Colon,
+            "dash" => MacStyle::Dash,
+            "dotted" | "cisco" => MacStyle::Dotted,
+            "bare" | "plain" => MacStyle::Bare,
+            _ => {
+                return Err(format!(
+                "无效的MAC输出格式 '{}'。可选: colon, dash, dotted, bare，可追加 -upper 或 -lower",
+                s
+            ))
+            }
+        };
+        Ok(MacFormat { style, uppercase })
+    }
+}
+
+impl fmt::Display for MacFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = match self.style {
+            MacStyle::Colon => "colon",
+            MacStyle::Dash => "dash",
+            MacStyle::Dotted => "dotted",
+            MacStyle::Bare => "bare",
+        };
+        if self.uppercase {
+            write!(f, "{}-upper", style)
+        } else {
+            f.write_str(style)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MacFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 按指定格式显示 MAC 地址，由 [`MacAddr::display`] 创建。
+pub struct FormattedMac {
+    mac: MacAddr,
+    format: MacFormat,
+}
+
+impl fmt::Display for FormattedMac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex: String = self
+            .mac
+            .0
+            .iter()
+            .map(|b| {
+                if self.format.uppercase {
+                    format!("{:02X}", b)
+                } else {
+                    format!("{:02x}", b)
+                }
+            })
+            .collect();
+        let out = match self.format.style {
+            MacStyle::Colon | MacStyle::Dash => {
+                let sep = if self.format.style == MacStyle::Colon {
+                    ":"
+                } else {
+                    "-"
+                };
+                (0..6)
+                    .map(|i| &hex[i * 2..i * 2 + 2])
+                    .collect::<Vec<_>>()
+                    .join(sep)
+            }
+            MacStyle::Dotted => (0..3)
+                .map(|i| &hex[i * 4..i * 4 + 4])
+                .collect::<Vec<_>>()
+                .join("."),
+            MacStyle::Bare => hex,
+        };
+        f.pad(&out)
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        MacAddr(octets)
+    }
+}
+
+impl From<MacAddr> for [u8; 6] {
+    fn from(mac: MacAddr) -> Self {
+        mac.0
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_mac_address(s)
+    }
+}
+
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MacAddr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 生成一个随机的、本地管理的MAC地址。
+pub fn generate_random_mac() -> MacAddr {
+    MacAddr(rand::random()).to_local_unicast()
+}
+
+/// 从种子确定性地派生一个本地管理的单播MAC地址。
+///
+/// 同一个种子在任何主机、任何版本上都会得到同一个地址。
+pub fn derive_mac(seed: &[u8]) -> MacAddr {
+    let digest = Sha256::new()
+        .chain_update(b"tap_mac_addr_test:")
+        .chain_update(seed)
+        .finalize();
+    let mut mac = [0u8; 6];
+    mac.copy_from_slice(&digest[..6]);
+    MacAddr(mac).to_local_unicast()
+}
+
+/// 读取本机的 machine-id，找不到时返回 `None`。
+pub fn machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+}
+
+/// 由接口名和本机 machine-id 派生MAC地址，使同一主机上的同名设备总是得到同一个地址。
+pub fn derive_mac_for_interface(name: &str) -> MacAddr {
+    let mut seed = name.as_bytes().to_vec();
+    match machine_id() {
+        Some(id) => {
+            seed.push(b'@');
+            seed.extend_from_slice(id.as_bytes());
+        }
+        None => warn!("无法读取 machine-id，仅使用接口名 '{}' 派生MAC地址", name),
+    }
+    derive_mac(&seed)
+}
+
+/// 将字符串解析为 MAC 地址。
+///
+/// 支持以下写法 (不区分大小写)：
+/// - 冒号或短横线分隔的六组，每组 1~2 位十六进制: `0a:0b:0c:0d:0e:0f`、`a-b-c-d-e-f`
+/// - Cisco 点分格式，三组各 4 位十六进制: `0a0b.0c0d.0e0f`
+/// - 不带分隔符的 12 位十六进制: `0a0b0c0d0e0f`
+pub fn parse_mac_address(s: &str) -> Result<MacAddr, String> {
+    let s = s.trim();
+    let invalid = || {
+        format!(
+            "无效的MAC地址格式 '{}'。期望的格式是 xx:xx:xx:xx:xx:xx、xx-xx-xx-xx-xx-xx、xxxx.xxxx.xxxx 或 xxxxxxxxxxxx",
+            s
+        )
+    };
+
+    // 下面按字节切片，先排除非 ASCII 输入以免切在字符中间
+    if !s.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut mac = [0u8; 6];
+    if let Some(sep) = s.chars().find(|c| *c == ':' || *c == '-') {
+        let parts: Vec<&str> = s.split(sep).collect();
+        if parts.len() != 6 {
+            return Err(invalid());
+        }
+        for (i, part) in parts.iter().enumerate() {
+            if part.is_empty() || part.len() > 2 {
+                return Err(format!("无效的分组 '{}': 每组应为 1~2 位十六进制数", part));
+            }
+            mac[i] = parse_hex_u8(part)?;
+        }
+    } else if s.contains('.') {
+        let parts: Vec<&str> = s.split('.').collect();
+        if parts.len() != 3 || parts.iter().any(|p| p.len() != 4) {
+            return Err(invalid());
+        }
+        for (i, part) in parts.iter().enumerate() {
+            mac[i * 2] = parse_hex_u8(&part[..2])?;
+            mac[i * 2 + 1] = parse_hex_u8(&part[2..])?;
+        }
+    } else {
+        if s.len() != 12 {
+            return Err(invalid());
+        }
+        for (i, byte) in mac.iter_mut().enumerate() {
+            *byte = parse_hex_u8(&s[i * 2..i * 2 + 2])?;
+        }
+    }
+    Ok(MacAddr(mac))
+}
+
+fn parse_hex_u8(part: &str) -> Result<u8, String> {
+    // from_str_radix 会接受前导 '+'，这里只允许纯十六进制数字
+    if !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("无效的十六进制部分 '{}'", part));
+    }
+    u8::from_str_radix(part, 16).map_err(|e| format!("无效的十六进制部分 '{}': {}", part, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: MacAddr = MacAddr([0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f]);
+
+    fn format(s: &str) -> MacFormat {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_colon_and_dash() {
+        assert_eq!(parse_mac_address("0a:0b:0c:0d:0e:0f"), Ok(MAC));
+        assert_eq!(parse_mac_address("0a-0b-0c-0d-0e-0f"), Ok(MAC));
+        assert_eq!(parse_mac_address("0A:0B:0c:0D:0e:0F"), Ok(MAC));
+        assert_eq!(parse_mac_address("  0a:0b:0c:0d:0e:0f\n"), Ok(MAC));
+    }
+
+    #[test]
+    fn parses_single_digit_groups() {
+        assert_eq!(parse_mac_address("a:b:c:d:e:f"), Ok(MAC));
+        assert_eq!(parse_mac_address("a-0b-c-0d-e-0f"), Ok(MAC));
+    }
+
+    #[test]
+    fn parses_dotted_and_bare() {
+        assert_eq!(parse_mac_address("0a0b.0c0d.0e0f"), Ok(MAC));
+        assert_eq!(parse_mac_address("0A0B.0C0D.0E0F"), Ok(MAC));
+        assert_eq!(parse_mac_address("0a0b0c0d0e0f"), Ok(MAC));
+        assert_eq!(parse_mac_address("0A0B0C0D0E0F"), Ok(MAC));
+    }
+
+    #[test]
+    fn rejects_mixed_separators() {
+        assert!(parse_mac_address("0a:0b-0c:0d:0e:0f").is_err());
+        assert!(parse_mac_address("0a-0b:0c-0d-0e-0f").is_err());
+        assert!(parse_mac_address("0a0b.0c0d:0e0f").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_group_count() {
+        assert!(parse_mac_address("0a:0b:0c:0d:0e").is_err());
+        assert!(parse_mac_address("0a:0b:0c:0d:0e:0f:10").is_err());
+        assert!(parse_mac_address("0a0b.0c0d").is_err());
+        assert!(parse_mac_address("0a0b.0c0d.0e0f.1011").is_err());
+        assert!(parse_mac_address("0a0b0c0d0e").is_err());
+        assert!(parse_mac_address("").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_digits() {
+        assert!(parse_mac_address("+a:0b:0c:0d:0e:0f").is_err());
+        assert!(parse_mac_address("+a0b.0c0d.0e0f").is_err());
+        assert!(parse_mac_address("+a0b0c0d0e0f").is_err());
+        assert!(parse_mac_address("0g:0b:0c:0d:0e:0f").is_err());
+        assert!(parse_mac_address("0a::0c:0d:0e:0f").is_err());
+        assert!(parse_mac_address("00a:0b:0c:0d:0e:0f").is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii() {
+        assert!(parse_mac_address("0a:0b:0c:0d:0e:0ｆ").is_err());
+        assert!(parse_mac_address("０a0b0c0d0e0f").is_err());
+        assert!(parse_mac_address("é0b.0c0d.0e0f").is_err());
+    }
+
+    #[test]
+    fn parses_output_formats() {
+        assert_eq!(format("colon"), MacFormat::default());
+        assert_eq!(
+            format("dash-upper"),
+            MacFormat {
+                style: MacStyle::Dash,
+                uppercase: true
+            }
+        );
+        assert_eq!(format("cisco").style, MacStyle::Dotted);
+        assert_eq!(format("plain").style, MacStyle::Bare);
+        assert_eq!(format("DOTTED-LOWER"), format("dotted"));
+        assert!("hyphen".parse::<MacFormat>().is_err());
+        assert!("colon-title".parse::<MacFormat>().is_err());
+    }
+
+    #[test]
+    fn displays_in_every_format() {
+        let mac = MacAddr([0xaa, 0xbb, 0xcc, 0x0d, 0x0e, 0x0f]);
+        let shown = |s: &str| mac.display(format(s)).to_string();
+        assert_eq!(shown("colon"), "aa:bb:cc:0d:0e:0f");
+        assert_eq!(shown("colon-upper"), "AA:BB:CC:0D:0E:0F");
+        assert_eq!(shown("dash"), "aa-bb-cc-0d-0e-0f");
+        assert_eq!(shown("dotted"), "aabb.cc0d.0e0f");
+        assert_eq!(shown("bare-upper"), "AABBCC0D0E0F");
+        assert_eq!(format("dash-upper").to_string(), "dash-upper");
+        assert_eq!(format("dotted-lower").to_string(), "dotted");
+    }
+}
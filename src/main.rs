@@ -1,132 +1,877 @@
-use anyhow::{Context, Result};
-use clap::Parser;
-use log::{info, warn};
-use std::process::Command;
-use tun_rs::{AsyncDevice, DeviceBuilder, Layer};
-
-const DEFAULT_TAP_NAME: &str = "tap0";
-const DEFAULT_MTU: i32 = 1500;
-
-/// 生成一个随机的、本地管理的MAC地址。
-fn generate_random_mac() -> [u8; 6] {
-    let mut mac: [u8; 6] = rand::random();
-    mac[0] |= 0x02; // 设置 "Locally Administered" 位
-    mac[0] &= 0xfe; // 清除 "Multicast" 位
-    mac
-}
-
-/// 将 "xx:xx:xx:xx:xx:xx" 格式的字符串解析为 [u8; 6]。
-fn parse_mac_address(s: &str) -> Result<[u8; 6], String> {
-    let parts: Vec<&str> = s.split(|c| c == ':' || c == '-').collect();
-    if parts.len() != 6 {
-        return Err(format!(
-            "无效的MAC地址格式 '{}'。期望的格式是 xx:xx:xx:xx:xx:xx",
-            s
-        ));
-    }
-    let mut mac = [0u8; 6];
-    for (i, part) in parts.iter().enumerate() {
-        mac[i] = u8::from_str_radix(part, 16)
-            .map_err(|e| format!("无效的十六进制部分 '{}': {}", part, e))?;
-    }
-    Ok(mac)
-}
-
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Cli {
-    /// TAP 设备的名称
-    #[arg(long, default_value = DEFAULT_TAP_NAME)]
-    name: String,
+mod cli;
+
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, FromArgMatches};
+use cli::{Cli, Command, CreateArgs, DisplayArgs, SetArgs};
+use futures::future::try_join_all;
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tap_mac_addr_test::addr::{check_duplicates, IpCidr};
+use tap_mac_addr_test::arp::ArpResponder;
+use tap_mac_addr_test::capture::Capture;
+use tap_mac_addr_test::change::change_mac;
+use tap_mac_addr_test::config::ConfigFile;
+use tap_mac_addr_test::control::ControlSocket;
+use tap_mac_addr_test::dump::Dump;
+use tap_mac_addr_test::info::{DeviceInfo, OutputFormat, RequestedMac};
+use tap_mac_addr_test::layer::DeviceLayer;
+use tap_mac_addr_test::list::{self, tun_devices, ListFormat};
+use tap_mac_addr_test::logging;
+use tap_mac_addr_test::mac::{
+    check_mac, derive_mac, derive_mac_for_interface, generate_random_mac, MacAddr, MacFormat,
+    MacPolicy, MacSource,
+};
+use tap_mac_addr_test::mtu::{check_mtu, verify_mtu};
+use tap_mac_addr_test::ndp::NdpResponder;
+use tap_mac_addr_test::netlink::{LinkMonitor, Netlink};
+use tap_mac_addr_test::queue::{log_stats, open_queues, FrameHandler, QueueStats};
+use tap_mac_addr_test::rotate::MacRotation;
+use tap_mac_addr_test::slaac::{
+    addr_gen_mode, format_interface_id, verify_link_local, LinkLocalCheck,
+};
+use tap_mac_addr_test::state::MacStateFile;
+use tap_mac_addr_test::sys;
+use tap_mac_addr_test::tun::{
+    check_deletable, device_layer, make_persistent, set_persist, tun_flags,
+};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tun_rs::{AsyncDevice, DeviceBuilder};
+
+pub const DEFAULT_TAP_NAME: &str = "tap0";
+
+/// 内核中的MAC地址与请求的不一致时的退出码。
+const EXIT_MAC_MISMATCH: i32 = 3;
+
+/// 等待内核生成链路本地地址的最长时间。
+const LINK_LOCAL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 删除持久设备 `name`。
+async fn delete_device(name: &str) -> Result<()> {
+    check_deletable(name)?;
+    let netlink = Netlink::connect()?;
+    let link = netlink.link_by_name(name).await?;
+    netlink.delete_link(link.index).await?;
+    info!("持久设备 '{}' 已删除", name);
+    Ok(())
+}
+
+/// L3 (TUN) 设备没有 MAC 地址，拒绝与之同时使用的 MAC 相关选项。
+fn check_layer_options(args: &CreateArgs) -> Result<()> {
+    if args.layer == DeviceLayer::L2 {
+        return Ok(());
+    }
+    let l2_only = [
+        ("--mac", args.mac.is_some()),
+        ("--mac-seed", args.mac_seed.is_some()),
+        ("--mac-prefix", args.mac_prefix.is_some()),
+        ("--mac-state", args.mac_state.is_some()),
+        ("--mac-fix", args.mac_fix),
+        ("--assign-link-local", args.assign_link_local),
+        ("--control", args.control.is_some()),
+        ("--rotate-mac", args.rotate_mac.is_some()),
+        ("--respond-ipv4", !args.respond_ipv4.is_empty()),
+        ("--respond-ipv6", !args.respond_ipv6.is_empty()),
+    ];
+    let used: Vec<&str> = l2_only
+        .iter()
+        .filter(|(_, used)| *used)
+        .map(|(name, _)| *name)
+        .collect();
+    if !used.is_empty() {
+        bail!(
+            "{} 只适用于 L2 (TAP) 模式，L3 (TUN) 设备没有 MAC 地址",
+            used.join(", ")
+        );
+    }
+    Ok(())
+}
 
-    /// TAP 设备的 MTU (最大传输单元)
-    #[arg(long, default_value_t = DEFAULT_MTU.try_into().unwrap())]
-    mtu: u16,
-
-    /// TAP 设备的 MAC 地址 (例如: 0a:0b:0c:0d:0e:0f)
-    /// 如果未提供，将生成一个随机的本地管理地址。
-    #[arg(long, value_parser = parse_mac_address)]
-    mac: Option<[u8; 6]>,
-}
-fn show_device_info(dev_name: &str) -> Result<()> {
-    info!("--- 执行 `ip addr show dev {}` ---", dev_name);
-    let output = Command::new("ip")
-        .arg("addr")
-        .arg("show")
-        .arg("dev")
-        .arg(dev_name)
-        .output()
-        .with_context(|| format!("执行 'ip addr show dev {}' 命令失败", dev_name))?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // 直接打印捕获到的标准输出，保留原始格式
-        print!("{}", stdout);
+/// 检查 clap 在合并配置文件之后无法再检查的选项关系。
+fn check_option_relations(args: &CreateArgs) -> Result<()> {
+    let conflicts = [
+        (
+            "--mac",
+            args.mac.is_some(),
+            "--mac-seed",
+            args.mac_seed.is_some(),
+        ),
+        (
+            "--mac",
+            args.mac.is_some(),
+            "--mac-prefix",
+            args.mac_prefix.is_some(),
+        ),
+        (
+            "--persist",
+            args.persist,
+            "--control",
+            args.control.is_some(),
+        ),
+        (
+            "--persist",
+            args.persist,
+            "--rotate-mac",
+            args.rotate_mac.is_some(),
+        ),
+        (
+            "--persist",
+            args.persist,
+            "--stats-interval",
+            args.stats_interval.is_some(),
+        ),
+        (
+            "--persist",
+            args.persist,
+            "--capture",
+            args.capture.is_some(),
+        ),
+        (
+            "--mac-policy allow",
+            args.mac_policy == MacPolicy::Allow,
+            "--mac-fix",
+            args.mac_fix,
+        ),
+        ("--persist", args.persist, "--dump", args.dump),
+        (
+            "--persist",
+            args.persist,
+            "--respond-ipv4",
+            !args.respond_ipv4.is_empty(),
+        ),
+        (
+            "--persist",
+            args.persist,
+            "--respond-ipv6",
+            !args.respond_ipv6.is_empty(),
+        ),
+    ];
+    for (a, a_used, b, b_used) in conflicts {
+        if a_used && b_used {
+            bail!("{} 不能与 {} 同时使用", a, b);
+        }
+    }
+    let requires = [
+        (
+            "--mac-history",
+            args.mac_history.is_some(),
+            "--rotate-mac",
+            args.rotate_mac.is_some(),
+        ),
+        ("--owner", args.owner.is_some(), "--persist", args.persist),
+        ("--group", args.group.is_some(), "--persist", args.persist),
+        ("--hex", args.hex, "--dump", args.dump),
+    ];
+    for (a, a_used, b, b_used) in requires {
+        if a_used && !b_used {
+            bail!("{} 需要同时指定 {}", a, b);
+        }
+    }
+    if args.queues == 0 {
+        bail!("队列数不能为 0");
+    }
+    Ok(())
+}
+
+/// 创建设备前检查合并后的全部参数。
+fn check_create_args(args: &CreateArgs) -> Result<()> {
+    if args.name.is_empty() || args.name.len() >= libc::IFNAMSIZ {
+        bail!(
+            "设备名 '{}' 的长度应为 1~{} 字节",
+            args.name,
+            libc::IFNAMSIZ - 1
+        );
+    }
+    check_option_relations(args)?;
+    check_layer_options(args)?;
+    let addresses: Vec<IpCidr> = args.ipv4.iter().chain(&args.ipv6).copied().collect();
+    check_duplicates(&addresses)?;
+    let responded = args
+        .respond_ipv4
+        .iter()
+        .map(|&ip| IpAddr::V4(ip))
+        .chain(args.respond_ipv6.iter().map(|&ip| IpAddr::V6(ip)));
+    for ip in responded {
+        if addresses.iter().any(|a| a.addr == ip) {
+            bail!(
+                "应答地址 {} 已分配给设备本身，内核不会为它发送 ARP 请求或邻居请求",
+                ip
+            );
+        }
+    }
+    let ipv6 = !args.ipv6.is_empty() || args.assign_link_local;
+    check_mtu(args.mtu().into(), args.layer, ipv6, args.jumbo)
+}
+
+/// 检查配置文件中的每个设备，不创建任何设备。
+fn check_config(path: &Path) -> Result<()> {
+    let config = ConfigFile::load(path)?;
+    let mut devices = Vec::with_capacity(config.devices.len());
+    for device in &config.devices {
+        let mut args = CreateArgs::defaults();
+        args.apply_config(device, None)
+            .and_then(|()| check_create_args(&args))
+            .with_context(|| format!("设备 '{}' 的定义无效", device.name))?;
+        println!(
+            "{:<16} {}  MTU {}  MAC {}",
+            args.name,
+            args.layer.kind(),
+            args.mtu(),
+            args.mac.map_or("(自动生成)".to_string(), |mac| mac
+                .display(args.display.mac_format)
+                .to_string())
+        );
+        devices.push(args);
+    }
+    check_devices(&devices)?;
+    info!(
+        "配置文件 '{}' 有效，共定义了 {} 个设备",
+        path.display(),
+        devices.len()
+    );
+    Ok(())
+}
+
+/// 根据命令行参数确定要使用的MAC地址及其来源。
+fn choose_mac(args: &CreateArgs) -> Result<(MacAddr, MacSource)> {
+    if let Some(mac) = args.mac {
+        info!(
+            "使用指定的MAC地址: {}",
+            mac.display(args.display.mac_format)
+        );
+        let mac = check_mac(mac, args.mac_policy, args.mac_fix)?;
+        return Ok((mac, MacSource::Supplied));
+    }
+
+    let Some(path) = &args.mac_state else {
+        return Ok(generate_mac(args));
+    };
+    let mut state = MacStateFile::open(path)?;
+    if let Some(mac) = state.get(&args.name) {
+        info!(
+            "从状态文件 '{}' 复用MAC地址: {}",
+            path.display(),
+            mac.display(args.display.mac_format)
+        );
+        if let Some(prefix) = args.mac_prefix.filter(|prefix| !prefix.matches(mac)) {
+            warn!(
+                "状态文件中的MAC地址不以前缀 {} 开头，仍按状态文件使用",
+                prefix
+            );
+        }
+        let mac = check_mac(mac, args.mac_policy, args.mac_fix)?;
+        return Ok((mac, MacSource::State));
+    }
+    let (mac, source) = generate_mac(args);
+    state.insert(&args.name, mac)?;
+    info!("已将MAC地址记录到状态文件 '{}'", path.display());
+    Ok((mac, source))
+}
+
+/// 在未指定 --mac 时，按种子派生或随机生成MAC地址，并套用 --mac-prefix。
+fn generate_mac(args: &CreateArgs) -> (MacAddr, MacSource) {
+    let (mac, description) = match args.mac_seed.as_deref() {
+        Some("") => (
+            derive_mac_for_interface(&args.name),
+            format!("由接口名 '{}' 和 machine-id 派生", args.name),
+        ),
+        Some(seed) => (
+            derive_mac(seed.as_bytes()),
+            format!("由种子 '{}' 派生", seed),
+        ),
+        None => (
+            generate_random_mac(),
+            "未提供MAC地址，已随机生成".to_string(),
+        ),
+    };
+    let mac = match args.mac_prefix {
+        Some(prefix) => prefix.apply(mac),
+        None => mac,
+    };
+    if args.mac_seed.is_some() {
+        info!(
+            "{}MAC地址: {}",
+            description,
+            mac.display(args.display.mac_format)
+        );
+        (mac, MacSource::Derived)
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // 使用 warn! 打印错误信息，因为设备可能创建成功但命令执行失败
-        warn!("获取设备 '{}' 信息失败:\n{}", dev_name, stderr.trim());
+        warn!(
+            "{}地址: {}",
+            description,
+            mac.display(args.display.mac_format)
+        );
+        (mac, MacSource::Random)
+    }
+}
+
+/// 显示由MAC地址派生的 EUI-64 链路本地地址，并与内核生成的比对。
+async fn check_link_local(
+    netlink: &Netlink,
+    index: u32,
+    dev_name: &str,
+    mac: MacAddr,
+    args: &CreateArgs,
+) -> Result<()> {
+    let link_local = mac.ipv6_link_local();
+    info!(
+        "EUI-64 接口标识: {}，链路本地地址: {}",
+        format_interface_id(mac.eui64()),
+        link_local
+    );
+
+    if args.assign_link_local {
+        let cidr = IpCidr {
+            addr: IpAddr::V6(link_local),
+            prefix_len: 64,
+        };
+        let existing = netlink.addresses(index).await?;
+        if existing.iter().any(|a| a.address == cidr.addr) {
+            info!("链路本地地址 {} 已由内核生成，无需分配", link_local);
+        } else {
+            netlink.add_address(index, cidr).await?;
+            info!("已为设备 '{}' 分配链路本地地址 {}", dev_name, cidr);
+        }
+    }
+
+    match verify_link_local(netlink, index, mac, LINK_LOCAL_TIMEOUT).await? {
+        LinkLocalCheck::Match => info!("内核中的链路本地地址与 EUI-64 预期一致"),
+        LinkLocalCheck::Mismatch(found) => {
+            let found: Vec<String> = found.iter().map(|a| a.to_string()).collect();
+            warn!(
+                "内核中的链路本地地址 {} 与 EUI-64 预期 {} 不一致 (addr_gen_mode = {})",
+                found.join(", "),
+                link_local,
+                addr_gen_mode(dev_name).map_or("未知".to_string(), |m| m.to_string())
+            );
+        }
+        LinkLocalCheck::Missing => warn!(
+            "{} 秒内设备 '{}' 上没有出现链路本地地址 (IPv6 可能被禁用，或 addr_gen_mode = {})",
+            LINK_LOCAL_TIMEOUT.as_secs(),
+            dev_name,
+            addr_gen_mode(dev_name).map_or("未知".to_string(), |m| m.to_string())
+        ),
+    }
+    Ok(())
+}
+
+/// 通过 rtnetlink 查询并打印设备信息。
+///
+/// JSON 模式下不打印，而是返回查询结果，由调用方把所有设备合成一个文档输出。
+async fn show_device_info(
+    netlink: &Netlink,
+    dev_name: &str,
+    requested: Option<RequestedMac>,
+    args: &CreateArgs,
+) -> Result<Option<DeviceInfo>> {
+    if args.display.output == OutputFormat::Json {
+        // JSON 模式下 stdout 只输出文档本身，查询失败直接报错
+        let mut device_info = DeviceInfo::query(netlink, dev_name).await?;
+        device_info.requested = requested;
+        return Ok(Some(device_info));
+    }
+
+    info!("--- 设备 '{}' 的信息 ---", dev_name);
+    match DeviceInfo::query(netlink, dev_name).await {
+        Ok(device_info) => print!(
+            "{}",
+            device_info.render(args.display.output, args.display.mac_format)
+        ),
+        // 使用 warn! 打印错误信息，因为设备可能创建成功但查询失败
+        Err(e) => warn!("获取设备 '{}' 信息失败: {:#}", dev_name, e),
     }
     info!("-------------------------------------");
+    Ok(None)
+}
+
+/// 列出所有 TAP/TUN 设备。
+fn list_devices(output: ListFormat, mac_format: MacFormat) -> Result<()> {
+    let devices = tun_devices()?;
+    print!("{}", list::render(&devices, output, mac_format));
+    Ok(())
+}
+
+/// 显示设备 `name` 的详细信息。
+async fn show_device(name: &str, display: &DisplayArgs) -> Result<()> {
+    let netlink = Netlink::connect()?;
+    let device_info = DeviceInfo::query(&netlink, name).await?;
+    print!("{}", device_info.render(display.output, display.mac_format));
+    Ok(())
+}
+
+/// 修改已存在设备的 MTU 和 MAC 地址。
+async fn set_device(args: &SetArgs) -> Result<()> {
+    let netlink = Netlink::connect()?;
+    if let Some(mtu) = args.mtu {
+        let link = netlink.link_by_name(&args.name).await?;
+        let layer = tun_flags(&args.name).map_or(DeviceLayer::L2, device_layer);
+        let ipv6 = netlink
+            .addresses(link.index)
+            .await?
+            .iter()
+            .any(|a| a.address.is_ipv6());
+        check_mtu(mtu.into(), layer, ipv6, args.jumbo)?;
+        netlink.set_mtu(link.index, mtu.into()).await?;
+        verify_mtu(&args.name, mtu.into())?;
+        info!("设备 '{}' 的 MTU 已设为 {}", args.name, mtu);
+    }
+
+    let new_mac = match args.mac {
+        Some(mac) => Some(check_mac(mac, MacPolicy::Strict, false)?),
+        None if args.random_mac => Some(generate_random_mac()),
+        None => None,
+    };
+    if let Some(new_mac) = new_mac {
+        let old_mac = change_mac(&netlink, &args.name, new_mac).await?;
+        info!(
+            "设备 '{}' 的MAC地址已从 {} 更换为 {}",
+            args.name,
+            old_mac.map(|m| m.to_string()).unwrap_or_default(),
+            new_mac
+        );
+    }
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 初始化日志记录器
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    logging::init();
 
     // 解析命令行参数
-    let cli = Cli::parse();
-
-    // 确定要使用的MAC地址
-    let node_mac = match cli.mac {
-        Some(mac) => {
-            let mac_str = mac
-                .iter()
-                .map(|b| format!("{:02x}", b))
-                .collect::<Vec<_>>()
-                .join(":");
-            info!("使用命令行提供的MAC地址: {}", mac_str);
-            mac
-        }
-        None => {
-            let mac = generate_random_mac();
-            let mac_str = mac
-                .iter()
-                .map(|b| format!("{:02x}", b))
-                .collect::<Vec<_>>()
-                .join(":");
-            warn!("未提供MAC地址，已生成随机地址: {}", mac_str);
-            mac
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match cli.command {
+        Command::Create(args) => {
+            let matches = matches
+                .subcommand_matches("create")
+                .expect("create 子命令的参数");
+            let devices = args.expand(matches)?;
+            create_devices(&devices).await
+        }
+        Command::Delete { name } => delete_device(&name).await,
+        Command::List { mac_format, output } => list_devices(output, mac_format),
+        Command::Show { name, display } => show_device(&name, &display).await,
+        Command::Set(args) => set_device(&args).await,
+        Command::CheckConfig { path } => check_config(&path),
+    }
+}
+
+/// 创建所有设备并运行，直到收到终止信号。
+///
+/// 任何一个设备创建失败时，已创建的设备都会被回滚 (删除)。
+async fn create_devices(devices: &[CreateArgs]) -> Result<()> {
+    for args in devices {
+        check_create_args(args).with_context(|| format!("设备 '{}' 的参数无效", args.name))?;
+    }
+    check_devices(devices)?;
+
+    // 同时运行多个设备时，在日志前加上设备名
+    let prefix = |args: &CreateArgs| {
+        if devices.len() > 1 {
+            args.name.clone()
+        } else {
+            String::new()
         }
     };
 
-    info!("正在创建TAP设备...");
-    info!("  名称: {}", cli.name);
-    info!("  MTU: {}", cli.mtu);
+    // 先确定所有设备的MAC地址，派生或复用得到的地址也可能相互冲突
+    let mut requested = Vec::with_capacity(devices.len());
+    let mut macs = HashMap::new();
+    for args in devices {
+        let mac = logging::with_device(prefix(args), async { requested_mac(args) }).await?;
+        if let Some(mac) = mac {
+            if let Some(other) = macs.insert(mac.mac, args.name.as_str()) {
+                bail!(
+                    "设备 '{}' 和 '{}' 的MAC地址都是 {}",
+                    other,
+                    args.name,
+                    mac.mac
+                );
+            }
+        }
+        requested.push(mac);
+    }
+
+    let netlink = Netlink::connect()?;
+    let mut created = Vec::with_capacity(devices.len());
+    for (args, requested) in devices.iter().zip(requested) {
+        match logging::with_device(prefix(args), build_device(args, requested, &netlink)).await {
+            Ok(device) => created.push(device),
+            Err(e) => {
+                if !created.is_empty() {
+                    error!(
+                        "设备 '{}' 创建失败，正在回滚已创建的 {} 个设备",
+                        args.name,
+                        created.len()
+                    );
+                }
+                // 设备尚未设为持久设备，drop 时由 `tun` 库删除
+                drop(created);
+                return Err(e);
+            }
+        }
+    }
+
+    let reports: Vec<DeviceInfo> = created
+        .iter_mut()
+        .filter_map(|device| device.json.take())
+        .collect();
+    match reports.as_slice() {
+        [] => {}
+        [report] if devices.len() == 1 => {
+            print!(
+                "{}",
+                report.render(OutputFormat::Json, devices[0].display.mac_format)
+            )
+        }
+        _ => print!("{}", DeviceInfo::render_json_array(&reports)),
+    }
+
+    if created.iter().any(|device| !device.mac_matches) {
+        drop(created);
+        std::process::exit(EXIT_MAC_MISMATCH);
+    }
+
+    let (persistent, running): (Vec<_>, Vec<_>) =
+        created.into_iter().partition(|device| device.args.persist);
+    persist_devices(&persistent)?;
+    if running.is_empty() {
+        return Ok(());
+    }
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let runs = try_join_all(running.into_iter().map(|device| {
+        logging::with_device(
+            prefix(device.args),
+            run_device(device, &netlink, shutdown_rx.clone()),
+        )
+    }));
+    tokio::pin!(runs);
+
+    info!("设备已启动，按 Ctrl+C 退出。");
+
+    // 等待终止信号 (Ctrl+C)；任何一个设备出错都会结束所有设备
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            info!("接收到终止信号，正在关闭程序...");
+            shutdown_tx.send_replace(());
+            runs.await?;
+        }
+        result = &mut runs => {
+            result?;
+        }
+    }
+
+    // 读取任务结束后设备的最后一个引用随之释放，`tun` 库的 Drop 实现会自动清理和关闭设备。
+    // 无需手动关闭。
+
+    Ok(())
+}
+
+/// 检查多个设备之间是否有冲突的名称、MAC 地址、IP 地址或控制套接字。
+fn check_devices(devices: &[CreateArgs]) -> Result<()> {
+    let mut names = HashSet::new();
+    let mut macs = HashMap::new();
+    let mut seeds = HashMap::new();
+    let mut controls = HashMap::new();
+    let mut captures = HashMap::new();
+    let mut addresses = Vec::new();
+    for args in devices {
+        if !names.insert(args.name.as_str()) {
+            bail!("设备 '{}' 被重复定义", args.name);
+        }
+        if let Some(mac) = args.mac {
+            if let Some(other) = macs.insert(mac, args.name.as_str()) {
+                bail!(
+                    "设备 '{}' 和 '{}' 使用了相同的MAC地址 {}",
+                    other,
+                    args.name,
+                    mac
+                );
+            }
+        }
+        if let Some(seed) = args.mac_seed.as_deref().filter(|seed| !seed.is_empty()) {
+            if let Some(other) = seeds.insert(seed, args.name.as_str()) {
+                bail!(
+                    "设备 '{}' 和 '{}' 使用了相同的MAC种子 '{}'，会派生出相同的MAC地址",
+                    other,
+                    args.name,
+                    seed
+                );
+            }
+        }
+        if let Some(control) = &args.control {
+            if let Some(other) = controls.insert(control, args.name.as_str()) {
+                bail!(
+                    "设备 '{}' 和 '{}' 使用了相同的控制套接字 '{}'",
+                    other,
+                    args.name,
+                    control.display()
+                );
+            }
+        }
+        if let Some(capture) = &args.capture {
+            if let Some(other) = captures.insert(capture, args.name.as_str()) {
+                bail!(
+                    "设备 '{}' 和 '{}' 使用了相同的捕获文件 '{}'",
+                    other,
+                    args.name,
+                    capture.display()
+                );
+            }
+        }
+        addresses.extend(args.ipv4.iter().chain(&args.ipv6).copied());
+    }
+    check_duplicates(&addresses)
+}
+
+/// 已创建并配置好、尚未开始运行的设备。
+struct CreatedDevice<'a> {
+    args: &'a CreateArgs,
+    device: AsyncDevice,
+    name: String,
+    mac_matches: bool,
+    /// JSON 输出模式下设备创建后的信息，所有设备创建完成后统一输出
+    json: Option<DeviceInfo>,
+}
+
+/// 确定要使用的MAC地址 (仅 L2)。
+fn requested_mac(args: &CreateArgs) -> Result<Option<RequestedMac>> {
+    match args.layer {
+        DeviceLayer::L2 => {
+            let (mac, source) = choose_mac(args)?;
+            Ok(Some(RequestedMac { mac, source }))
+        }
+        DeviceLayer::L3 => Ok(None),
+    }
+}
+
+/// 以 `requested` 的MAC地址创建一个设备，回读 MTU 和 MAC 地址，分配 IP 地址并显示设备信息。
+async fn build_device<'a>(
+    args: &'a CreateArgs,
+    requested: Option<RequestedMac>,
+    netlink: &Netlink,
+) -> Result<CreatedDevice<'a>> {
+    let addresses: Vec<IpCidr> = args.ipv4.iter().chain(&args.ipv6).copied().collect();
+    let mtu = args.mtu();
+    let kind = args.layer.kind();
+
+    info!("正在创建{}设备...", kind);
+    info!("  名称: {}", args.name);
+    info!("  MTU: {}", mtu);
+    info!("  队列数: {}", args.queues);
 
     // 使用 `tun` 库的 Device::builder()
     let mut builder = DeviceBuilder::new()
-        .name(cli.name)
-        .mac_addr(node_mac)
-        .layer(Layer::L2)
-        .mtu(cli.mtu);
+        .name(args.name.clone())
+        .layer(args.layer.into())
+        .mtu(mtu);
+    if let Some(requested) = requested {
+        builder = builder.mac_addr(requested.mac.octets());
+    }
+    if args.queues > 1 {
+        builder = builder.multi_queue(true);
+    }
 
-    let device = builder.build_async().context("创建TAP设备失败")?;
-    info!("TAP设备 '{}' 创建成功!", device.name()?);
+    let device = builder
+        .build_async()
+        .with_context(|| format!("创建{}设备失败", kind))?;
+    let dev_name = device.name()?;
+    info!("{}设备 '{}' 创建成功!", kind, dev_name);
 
-    show_device_info(&device.name()?)?;
+    // 从内核回读 MTU，确认 mtu() 真正生效
+    verify_mtu(&dev_name, mtu.into())?;
 
-    info!("设备已启动，按 Ctrl+C 退出。");
+    // 从内核回读硬件地址，确认 mac_addr() 真正生效
+    let mut mac_matches = true;
+    if let Some(requested) = requested {
+        let actual_mac = sys::hardware_address(&dev_name)
+            .with_context(|| format!("读取设备 '{}' 的硬件地址失败", dev_name))?;
+        mac_matches = actual_mac == requested.mac;
+        if mac_matches {
+            info!(
+                "已确认内核中的MAC地址与请求一致: {}",
+                actual_mac.display(args.display.mac_format)
+            );
+        } else {
+            error!(
+                "MAC地址未生效: 请求 {}，内核报告 {}",
+                requested.mac.display(args.display.mac_format),
+                actual_mac.display(args.display.mac_format)
+            );
+        }
+    }
+
+    let index = sys::if_index(&dev_name)?;
+    for cidr in &addresses {
+        netlink.add_address(index, *cidr).await?;
+        info!("已为设备 '{}' 分配地址 {}", dev_name, cidr);
+    }
 
-    // 等待终止信号 (Ctrl+C)
-    tokio::signal::ctrl_c().await?;
+    if let Some(requested) = requested {
+        check_link_local(netlink, index, &dev_name, requested.mac, args).await?;
+    }
 
-    info!("接收到终止信号，正在关闭程序...");
+    let json = show_device_info(netlink, &dev_name, requested, args).await?;
 
-    // 当 `device` 变量离开作用域时，`tun` 库的 Drop 实现会自动清理和关闭设备。
-    // 无需手动关闭。
+    Ok(CreatedDevice {
+        args,
+        device,
+        name: dev_name,
+        mac_matches,
+        json,
+    })
+}
+
+/// 将设备设为持久设备。任何一个失败时，撤销已经设置的持久化，让所有设备随进程退出而删除。
+fn persist_devices(devices: &[CreatedDevice]) -> Result<()> {
+    for (i, created) in devices.iter().enumerate() {
+        let args = created.args;
+        if let Err(e) = make_persistent(&created.device, &created.name, args.owner, args.group) {
+            for done in &devices[..i] {
+                if let Err(e) = set_persist(&done.device, false) {
+                    warn!("撤销设备 '{}' 的持久化失败: {}", done.name, e);
+                }
+            }
+            return Err(e);
+        }
+    }
+    for created in devices {
+        info!(
+            "设备 '{}' 已设为持久设备，程序退出后仍然保留 (删除: {} delete {})",
+            created.name,
+            env!("CARGO_BIN_NAME"),
+            created.name
+        );
+    }
+    Ok(())
+}
+
+/// 运行一个设备: 处理控制套接字、定时轮换和队列读取，直到 `shutdown` 收到通知或出错。
+async fn run_device(
+    created: CreatedDevice<'_>,
+    netlink: &Netlink,
+    mut shutdown: watch::Receiver<()>,
+) -> Result<()> {
+    let CreatedDevice {
+        args,
+        device,
+        name: dev_name,
+        ..
+    } = created;
+
+    // 设备当前的MAC地址 (L3 设备没有，固定为全零)。逐帧处理的处理器从这里读取，
+    // 而不是每帧都向内核查询；接口变化通知会让它跟上任何进程做的更换。
+    // 先订阅通知再读取初始地址，避免错过两者之间的更换。
+    let monitor = match args.layer {
+        DeviceLayer::L2 => Some(LinkMonitor::connect()?),
+        DeviceLayer::L3 => None,
+    };
+    let mac = watch::Sender::new(match args.layer {
+        DeviceLayer::L2 => sys::hardware_address(&dev_name)
+            .with_context(|| format!("读取设备 '{}' 的硬件地址失败", dev_name))?,
+        DeviceLayer::L3 => MacAddr::ZERO,
+    });
+    let index = sys::if_index(&dev_name)?;
+    let follow_mac = async {
+        match monitor {
+            Some(monitor) => monitor.follow_mac(index, &mac).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let control = args
+        .control
+        .as_deref()
+        .map(ControlSocket::bind)
+        .transpose()?;
+    let serve_control = async {
+        match &control {
+            Some(control) => control.serve(netlink.clone(), dev_name.clone()).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let rotation = args.rotate_mac.map(|interval| MacRotation {
+        interval,
+        prefix: args.mac_prefix,
+        history: args.mac_history.clone(),
+    });
+    let rotate_mac = async {
+        match &rotation {
+            Some(rotation) => rotation.run(netlink.clone(), dev_name.clone()).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let mut handlers: Vec<Box<dyn FrameHandler>> = Vec::new();
+    if let Some(path) = &args.capture {
+        let mac = (args.layer == DeviceLayer::L2).then(|| *mac.borrow());
+        handlers.push(Box::new(Capture::create(path, args.layer, &dev_name, mac)?));
+        info!("正在将设备 '{}' 上的帧写入 '{}'", dev_name, path.display());
+    }
+    if args.dump {
+        let mac = (args.layer == DeviceLayer::L2).then(|| mac.subscribe());
+        handlers.push(Box::new(Dump::new(args.layer, args.hex, mac)));
+    }
+    if !args.respond_ipv4.is_empty() {
+        handlers.push(Box::new(ArpResponder::new(
+            mac.subscribe(),
+            &args.respond_ipv4,
+        )));
+    }
+    if !args.respond_ipv6.is_empty() {
+        handlers.push(Box::new(NdpResponder::new(
+            mac.subscribe(),
+            &args.respond_ipv6,
+        )));
+    }
+    let handlers = Arc::new(handlers);
+
+    // 每个队列一个读取任务
+    let queues = open_queues(device, args.queues as usize)?;
+    let stats: Vec<Arc<QueueStats>> = queues.iter().map(|q| q.stats.clone()).collect();
+    queues[0].send_startup(&handlers).await?;
+    let mut readers = JoinSet::new();
+    for queue in queues {
+        let handlers = handlers.clone();
+        readers.spawn(logging::inherit(async move { queue.run(&handlers).await }));
+    }
+
+    let report_stats = async {
+        match args.stats_interval {
+            Some(interval) => {
+                let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+                loop {
+                    ticker.tick().await;
+                    log_stats(&stats);
+                }
+            }
+            None => std::future::pending().await,
+        }
+    };
+
+    info!("设备 '{}' 已启动", dev_name);
+
+    // 同时处理控制套接字上的命令、定时轮换和队列读取，直到收到关闭通知
+    tokio::select! {
+        _ = shutdown.changed() => {}
+        result = serve_control => result.context("控制套接字出错")?,
+        result = rotate_mac => result?,
+        result = follow_mac => result?,
+        Some(result) = readers.join_next() => result??,
+        () = report_stats => {}
+    }
 
+    readers.shutdown().await;
+    log_stats(&stats);
     Ok(())
 }
@@ -0,0 +1,97 @@
+//! MTU 的取值范围检查与回读。
+
+use crate::layer::DeviceLayer;
+use crate::sys;
+use anyhow::{bail, Context, Result};
+
+/// 默认 MTU，即标准以太网帧的载荷上限。
+pub const DEFAULT_MTU: u16 = 1500;
+
+/// 使用 `--jumbo` 且未指定 MTU 时的默认值。
+pub const JUMBO_MTU: u16 = 9000;
+
+/// IPv4 要求的最小 MTU (RFC 791)，也是内核对 TUN/TAP 设备的下限。
+pub const IPV4_MIN_MTU: u32 = 68;
+
+/// IPv6 要求的最小 MTU (RFC 8200)。低于此值时内核会在接口上禁用 IPv6。
+pub const IPV6_MIN_MTU: u32 = 1280;
+
+/// 内核中 TUN/TAP 设备的 MTU 上限: 65535 减去链路层头部长度。
+pub fn max_mtu(layer: DeviceLayer) -> u32 {
+    match layer {
+        DeviceLayer::L2 => 65535 - 14,
+        DeviceLayer::L3 => 65535,
+    }
+}
+
+/// 检查 `mtu` 是否适用于 `layer` 层的设备。
+///
+/// `ipv6` 表示接口上会配置 IPv6 地址；超过标准以太网 MTU 的值需要 `jumbo`。
+pub fn check_mtu(mtu: u32, layer: DeviceLayer, ipv6: bool, jumbo: bool) -> Result<()> {
+    if mtu < IPV4_MIN_MTU {
+        bail!("MTU {} 过小，不能小于 {}", mtu, IPV4_MIN_MTU);
+    }
+    if ipv6 && mtu < IPV6_MIN_MTU {
+        bail!(
+            "接口上配置了 IPv6，MTU {} 不能小于 {} (否则内核会在该接口上禁用 IPv6)",
+            mtu,
+            IPV6_MIN_MTU
+        );
+    }
+    let max = max_mtu(layer);
+    if mtu > max {
+        bail!("MTU {} 超过了内核对{}设备的上限 {}", mtu, layer.kind(), max);
+    }
+    if mtu > DEFAULT_MTU as u32 && !jumbo {
+        bail!(
+            "MTU {} 超过了标准以太网的 {}，如需巨型帧请加上 --jumbo",
+            mtu,
+            DEFAULT_MTU
+        );
+    }
+    Ok(())
+}
+
+/// 从内核回读接口 `name` 的 MTU，确认与 `expected` 一致。
+pub fn verify_mtu(name: &str, expected: u32) -> Result<()> {
+    let actual = sys::mtu(name).with_context(|| format!("读取设备 '{}' 的 MTU 失败", name))?;
+    if actual != expected {
+        bail!("MTU 未生效: 请求 {}，内核报告 {}", expected, actual);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DeviceLayer::{L2, L3};
+
+    #[test]
+    fn enforces_ipv4_minimum() {
+        assert!(check_mtu(67, L2, false, false).is_err());
+        assert!(check_mtu(68, L2, false, false).is_ok());
+        assert!(check_mtu(68, L3, false, false).is_ok());
+    }
+
+    #[test]
+    fn enforces_ipv6_minimum() {
+        assert!(check_mtu(1279, L2, true, false).is_err());
+        assert!(check_mtu(1279, L2, false, false).is_ok());
+        assert!(check_mtu(1280, L2, true, false).is_ok());
+    }
+
+    #[test]
+    fn requires_jumbo_above_ethernet_mtu() {
+        assert!(check_mtu(1500, L2, true, false).is_ok());
+        assert!(check_mtu(1501, L2, true, false).is_err());
+        assert!(check_mtu(1501, L2, true, true).is_ok());
+    }
+
+    #[test]
+    fn enforces_kernel_maximum_per_layer() {
+        assert!(check_mtu(65521, L2, false, true).is_ok());
+        assert!(check_mtu(65522, L2, false, true).is_err());
+        assert!(check_mtu(65535, L3, false, true).is_ok());
+        assert!(check_mtu(65536, L3, false, true).is_err());
+    }
+}
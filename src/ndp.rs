@@ -0,0 +1,186 @@
+//! 用户态邻居发现应答: ARP 应答在 IPv6 上的对应物。
+
+use crate::decode::{parse_ethernet, parse_ipv6, parse_neighbor_solicitation};
+use crate::mac::MacAddr;
+use crate::packet::{self, ETHERTYPE_IPV6, IPPROTO_ICMPV6, NA_FLAG_OVERRIDE, NA_FLAG_SOLICITED};
+use crate::queue::FrameHandler;
+use anyhow::Result;
+use log::info;
+use std::net::{IpAddr, Ipv6Addr};
+use tokio::sync::watch;
+
+/// 以设备的 MAC 地址作为目标链路层地址，应答对 `addresses` 的邻居请求 (包括 DAD 探测)，
+/// 并在启动时为它们发送主动 NA。
+///
+/// 设备地址由 `mac` 提供，它随接口变化通知更新。
+pub struct NdpResponder {
+    mac: watch::Receiver<MacAddr>,
+    addresses: Vec<Ipv6Addr>,
+}
+
+impl NdpResponder {
+    pub fn new(mac: watch::Receiver<MacAddr>, addresses: &[Ipv6Addr]) -> Self {
+        NdpResponder {
+            mac,
+            addresses: addresses.to_vec(),
+        }
+    }
+
+    fn mac(&self) -> MacAddr {
+        *self.mac.borrow()
+    }
+}
+
+impl FrameHandler for NdpResponder {
+    fn handle(&self, frame: &[u8]) -> Result<Option<Vec<u8>>> {
+        let Some(eth) = parse_ethernet(frame) else {
+            return Ok(None);
+        };
+        if eth.ethertype != ETHERTYPE_IPV6 || !eth.vlans.is_empty() {
+            return Ok(None);
+        }
+        let Some(ip) = parse_ipv6(eth.payload) else {
+            return Ok(None);
+        };
+        // RFC 4861 7.1.1: 跳数限制不是 255 的 ND 报文可能来自其他链路，必须丢弃
+        if ip.protocol != IPPROTO_ICMPV6 || ip.hop_limit != 255 || ip.fragment {
+            return Ok(None);
+        }
+        let Some(ns) = parse_neighbor_solicitation(ip.payload) else {
+            return Ok(None);
+        };
+        if !self.addresses.contains(&ns.target) {
+            return Ok(None);
+        }
+        let IpAddr::V6(src) = ip.src else {
+            return Ok(None);
+        };
+
+        let mac = self.mac();
+        if src.is_unspecified() {
+            // DAD 探测: 发往所有节点，不置 Solicited 标志 (RFC 4861 7.2.4)
+            info!(
+                "应答对 {} 的重复地址检测，通告其已被 {} 使用",
+                ns.target, mac
+            );
+            return Ok(Some(packet::unsolicited_na(mac, ns.target)));
+        }
+        info!(
+            "应答邻居请求: {} is-at {} (询问方 {} {})",
+            ns.target, mac, src, eth.src
+        );
+        Ok(Some(packet::neighbor_advertisement(
+            ns.source_mac.unwrap_or(eth.src),
+            mac,
+            ns.target,
+            src,
+            ns.target,
+            NA_FLAG_SOLICITED | NA_FLAG_OVERRIDE,
+        )))
+    }
+
+    fn startup(&self) -> Result<Vec<Vec<u8>>> {
+        let mac = self.mac();
+        info!(
+            "以MAC地址 {} 应答对 {} 的邻居请求",
+            mac,
+            self.addresses
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(self
+            .addresses
+            .iter()
+            .map(|&ip| packet::unsolicited_na(mac, ip))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::{
+        icmpv6_checksum, ALL_NODES, ETHERNET_HEADER_LEN, ICMPV6_NEIGHBOR_SOLICITATION,
+    };
+
+    const MAC: MacAddr = MacAddr::new([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+    const PEER: MacAddr = MacAddr::new([0x52, 0x54, 0x00, 0xab, 0xcd, 0xef]);
+
+    fn ip(s: &str) -> Ipv6Addr {
+        s.parse().unwrap()
+    }
+
+    fn responder() -> NdpResponder {
+        let (_, mac) = watch::channel(MAC);
+        NdpResponder::new(mac, &[ip("fd00::1")])
+    }
+
+    /// 构造 `src` 询问 `target` 的邻居请求帧，`src` 为 `::` 时即 DAD 探测 (不带源链路层地址选项)。
+    fn solicitation(src: Ipv6Addr, target: Ipv6Addr, hop_limit: u8) -> Vec<u8> {
+        let dst = ip("ff02::1:ff00:1");
+        let mut icmp = vec![ICMPV6_NEIGHBOR_SOLICITATION, 0, 0, 0, 0, 0, 0, 0];
+        icmp.extend_from_slice(&target.octets());
+        if !src.is_unspecified() {
+            icmp.extend_from_slice(&[1, 1]);
+            icmp.extend_from_slice(&PEER.octets());
+        }
+        let checksum = icmpv6_checksum(src, dst, &icmp);
+        icmp[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&[0x33, 0x33, 0xff, 0, 0, 1]);
+        frame.extend_from_slice(&PEER.octets());
+        frame.extend_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+        frame.extend_from_slice(&[0x60, 0, 0, 0]);
+        frame.extend_from_slice(&(icmp.len() as u16).to_be_bytes());
+        frame.push(IPPROTO_ICMPV6);
+        frame.push(hop_limit);
+        frame.extend_from_slice(&src.octets());
+        frame.extend_from_slice(&dst.octets());
+        frame.extend_from_slice(&icmp);
+        frame
+    }
+
+    /// NA 帧的以太网目的地址、IPv6 目的地址和标志。
+    fn advertisement(frame: &[u8]) -> (MacAddr, Ipv6Addr, u32) {
+        let eth = parse_ethernet(frame).unwrap();
+        let ip = parse_ipv6(eth.payload).unwrap();
+        let IpAddr::V6(dst) = ip.dst else {
+            unreachable!()
+        };
+        let flags = u32::from_be_bytes(ip.payload[4..8].try_into().unwrap());
+        (eth.dst, dst, flags)
+    }
+
+    #[test]
+    fn answers_solicitation_to_sender() {
+        let frame = solicitation(ip("fd00::2"), ip("fd00::1"), 255);
+        let reply = responder().handle(&frame).unwrap().unwrap();
+        assert_eq!(
+            advertisement(&reply),
+            (PEER, ip("fd00::2"), NA_FLAG_SOLICITED | NA_FLAG_OVERRIDE)
+        );
+        assert_eq!(&reply[ETHERNET_HEADER_LEN + 40 + 26..], &MAC.octets());
+    }
+
+    #[test]
+    fn answers_dad_probe_to_all_nodes() {
+        let frame = solicitation(Ipv6Addr::UNSPECIFIED, ip("fd00::1"), 255);
+        let reply = responder().handle(&frame).unwrap().unwrap();
+        let (eth_dst, dst, flags) = advertisement(&reply);
+        assert_eq!(eth_dst, packet::ipv6_multicast_mac(ALL_NODES));
+        assert_eq!(dst, ALL_NODES);
+        assert_eq!(flags & NA_FLAG_SOLICITED, 0);
+    }
+
+    #[test]
+    fn ignores_other_targets_and_hop_limits() {
+        let responder = responder();
+        let other = solicitation(ip("fd00::2"), ip("fd00::3"), 255);
+        assert!(responder.handle(&other).unwrap().is_none());
+        let forwarded = solicitation(ip("fd00::2"), ip("fd00::1"), 64);
+        assert!(responder.handle(&forwarded).unwrap().is_none());
+    }
+}
@@ -0,0 +1,249 @@
+//! 通过 rtnetlink 查询接口信息，不依赖 iproute2。
+
+use crate::addr::IpCidr;
+use crate::mac::MacAddr;
+use anyhow::{anyhow, bail, Context, Result};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::{StreamExt, TryStreamExt};
+use log::info;
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use netlink_packet_route::nlas::address::Nla as AddressNla;
+use netlink_packet_route::nlas::link::Nla as LinkNla;
+use netlink_packet_route::{AddressMessage, LinkMessage, RtnlMessage, AF_INET, AF_INET6};
+use netlink_sys::{AsyncSocket, SocketAddr};
+use rtnetlink::constants::RTMGRP_LINK;
+use rtnetlink::Handle;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::sync::watch;
+
+/// 一个网络接口的链路层信息 (对应 `ip link`)。
+#[derive(Clone, Debug, Default)]
+pub struct LinkInfo {
+    pub index: u32,
+    pub name: String,
+    /// ARPHRD_* 链路类型
+    pub link_type: u16,
+    /// IFF_* 标志位
+    pub flags: u32,
+    pub mtu: Option<u32>,
+    pub mac: Option<MacAddr>,
+    pub broadcast: Option<MacAddr>,
+    pub qdisc: Option<String>,
+    pub oper_state: Option<String>,
+    pub tx_queue_len: Option<u32>,
+}
+
+/// 接口上的一个 IP 地址 (对应 `ip addr`)。
+#[derive(Clone, Debug)]
+pub struct AddressInfo {
+    pub address: IpAddr,
+    pub prefix_len: u8,
+    /// RT_SCOPE_* 作用域
+    pub scope: u8,
+    pub broadcast: Option<IpAddr>,
+    pub label: Option<String>,
+}
+
+/// rtnetlink 连接。连接任务在创建时被派生到当前 tokio 运行时上。
+#[derive(Clone)]
+pub struct Netlink {
+    handle: Handle,
+}
+
+impl Netlink {
+    pub fn connect() -> Result<Self> {
+        let (connection, handle, _) =
+            rtnetlink::new_connection().context("建立 rtnetlink 连接失败")?;
+        tokio::spawn(connection);
+        Ok(Netlink { handle })
+    }
+
+    /// 按名称查询接口。
+    pub async fn link_by_name(&self, name: &str) -> Result<LinkInfo> {
+        let mut links = self
+            .handle
+            .link()
+            .get()
+            .match_name(name.to_string())
+            .execute();
+        let msg = links
+            .try_next()
+            .await
+            .with_context(|| format!("查询接口 '{}' 失败", name))?
+            .ok_or_else(|| anyhow!("接口 '{}' 不存在", name))?;
+        Ok(parse_link(msg))
+    }
+
+    /// 启用或禁用接口 (对应 `ip link set up|down`)。
+    pub async fn set_up(&self, index: u32, up: bool) -> Result<()> {
+        let request = self.handle.link().set(index);
+        let request = if up { request.up() } else { request.down() };
+        request
+            .execute()
+            .await
+            .with_context(|| format!("{}接口 #{} 失败", if up { "启用" } else { "禁用" }, index))
+    }
+
+    /// 修改接口的硬件地址 (对应 `ip link set address`)。
+    pub async fn set_address(&self, index: u32, mac: MacAddr) -> Result<()> {
+        self.handle
+            .link()
+            .set(index)
+            .address(mac.octets().to_vec())
+            .execute()
+            .await
+            .with_context(|| format!("设置接口 #{} 的MAC地址为 {} 失败", index, mac))
+    }
+
+    /// 修改接口的 MTU (对应 `ip link set mtu`)。
+    pub async fn set_mtu(&self, index: u32, mtu: u32) -> Result<()> {
+        self.handle
+            .link()
+            .set(index)
+            .mtu(mtu)
+            .execute()
+            .await
+            .with_context(|| format!("设置接口 #{} 的 MTU 为 {} 失败", index, mtu))
+    }
+
+    /// 删除接口 (对应 `ip link delete`)。
+    pub async fn delete_link(&self, index: u32) -> Result<()> {
+        self.handle
+            .link()
+            .del(index)
+            .execute()
+            .await
+            .with_context(|| format!("删除接口 #{} 失败", index))
+    }
+
+    /// 为接口添加一个地址 (对应 `ip addr add`)。
+    pub async fn add_address(&self, index: u32, cidr: IpCidr) -> Result<()> {
+        self.handle
+            .address()
+            .add(index, cidr.addr, cidr.prefix_len)
+            .execute()
+            .await
+            .with_context(|| format!("为接口 #{} 添加地址 {} 失败", index, cidr))
+    }
+
+    /// 查询接口 `index` 上的所有地址。
+    pub async fn addresses(&self, index: u32) -> Result<Vec<AddressInfo>> {
+        let msgs: Vec<AddressMessage> = self
+            .handle
+            .address()
+            .get()
+            .set_link_index_filter(index)
+            .execute()
+            .try_collect()
+            .await
+            .with_context(|| format!("查询接口 #{} 的地址失败", index))?;
+        Ok(msgs.into_iter().filter_map(parse_address).collect())
+    }
+}
+
+/// 订阅了接口变化通知的 rtnetlink 连接 (对应 `ip monitor link`)。
+pub struct LinkMonitor {
+    messages: UnboundedReceiver<(NetlinkMessage<RtnlMessage>, SocketAddr)>,
+}
+
+impl LinkMonitor {
+    pub fn connect() -> Result<Self> {
+        let (mut connection, _, messages) =
+            rtnetlink::new_connection().context("建立 rtnetlink 连接失败")?;
+        connection
+            .socket_mut()
+            .socket_mut()
+            .bind(&SocketAddr::new(0, RTMGRP_LINK))
+            .context("订阅接口变化通知失败")?;
+        tokio::spawn(connection);
+        Ok(LinkMonitor { messages })
+    }
+
+    /// 把接口 `index` 的硬件地址变化同步到 `mac`，包括其他进程 (`set --mac`、`ip link`) 做的更换。
+    /// 只在通知连接断开时返回。
+    pub async fn follow_mac(mut self, index: u32, mac: &watch::Sender<MacAddr>) -> Result<()> {
+        while let Some((msg, _)) = self.messages.next().await {
+            let NetlinkPayload::InnerMessage(RtnlMessage::NewLink(msg)) = msg.payload else {
+                continue;
+            };
+            let link = parse_link(msg);
+            let Some(new_mac) = link.mac.filter(|_| link.index == index) else {
+                continue;
+            };
+            mac.send_if_modified(|current| {
+                if *current == new_mac {
+                    return false;
+                }
+                info!("设备 '{}' 的MAC地址已变为 {}", link.name, new_mac);
+                *current = new_mac;
+                true
+            });
+        }
+        bail!("接口变化通知的 rtnetlink 连接已断开")
+    }
+}
+
+fn parse_link(msg: LinkMessage) -> LinkInfo {
+    let mut info = LinkInfo {
+        index: msg.header.index,
+        link_type: msg.header.link_layer_type,
+        flags: msg.header.flags,
+        ..Default::default()
+    };
+    for nla in msg.nlas {
+        match nla {
+            LinkNla::IfName(name) => info.name = name,
+            LinkNla::Mtu(mtu) => info.mtu = Some(mtu),
+            LinkNla::Address(bytes) => info.mac = mac_from_bytes(&bytes),
+            LinkNla::Broadcast(bytes) => info.broadcast = mac_from_bytes(&bytes),
+            LinkNla::Qdisc(qdisc) => info.qdisc = Some(qdisc),
+            LinkNla::OperState(state) => {
+                info.oper_state = Some(format!("{:?}", state).to_uppercase())
+            }
+            LinkNla::TxQueueLen(len) => info.tx_queue_len = Some(len),
+            _ => {}
+        }
+    }
+    info
+}
+
+fn parse_address(msg: AddressMessage) -> Option<AddressInfo> {
+    let family = msg.header.family;
+    let mut address = None;
+    let mut local = None;
+    let mut broadcast = None;
+    let mut label = None;
+    for nla in msg.nlas {
+        match nla {
+            AddressNla::Address(bytes) => address = ip_from_bytes(family, &bytes),
+            AddressNla::Local(bytes) => local = ip_from_bytes(family, &bytes),
+            AddressNla::Broadcast(bytes) => broadcast = ip_from_bytes(family, &bytes),
+            AddressNla::Label(l) => label = Some(l),
+            _ => {}
+        }
+    }
+    // 点对点接口上 IFA_ADDRESS 是对端地址，本端地址在 IFA_LOCAL 中
+    Some(AddressInfo {
+        address: local.or(address)?,
+        prefix_len: msg.header.prefix_len,
+        scope: msg.header.scope,
+        broadcast,
+        label,
+    })
+}
+
+fn mac_from_bytes(bytes: &[u8]) -> Option<MacAddr> {
+    <[u8; 6]>::try_from(bytes).ok().map(MacAddr::from)
+}
+
+fn ip_from_bytes(family: u8, bytes: &[u8]) -> Option<IpAddr> {
+    match family as u16 {
+        AF_INET => <[u8; 4]>::try_from(bytes)
+            .ok()
+            .map(|b| IpAddr::V4(Ipv4Addr::from(b))),
+        AF_INET6 => <[u8; 16]>::try_from(bytes)
+            .ok()
+            .map(|b| IpAddr::V6(Ipv6Addr::from(b))),
+        _ => None,
+    }
+}
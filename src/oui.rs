@@ -0,0 +1,111 @@
+//! 厂商前缀 (OUI) 感知的MAC地址生成。
+
+use crate::mac::MacAddr;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// 常见虚拟化平台使用的前缀。
+pub const PRESETS: &[(&str, &[u8])] = &[
+    ("qemu", &[0x52, 0x54, 0x00]),
+    ("kvm", &[0x52, 0x54, 0x00]),
+    ("xen", &[0x00, 0x16, 0x3e]),
+    ("virtualbox", &[0x08, 0x00, 0x27]),
+    ("vmware", &[0x00, 0x50, 0x56]),
+    ("hyperv", &[0x00, 0x15, 0x5d]),
+    ("parallels", &[0x00, 0x1c, 0x42]),
+    ("docker", &[0x02, 0x42]),
+];
+
+/// MAC 地址前缀，长度为 1~5 字节。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MacPrefix {
+    bytes: [u8; 5],
+    len: usize,
+}
+
+impl MacPrefix {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// 用前缀覆盖 `mac` 的前几个字节，其余字节保持不变。
+    pub fn apply(&self, mac: MacAddr) -> MacAddr {
+        let mut octets = mac.octets();
+        octets[..self.len].copy_from_slice(self.as_bytes());
+        MacAddr::new(octets)
+    }
+
+    /// `mac` 是否以该前缀开头。
+    pub fn matches(&self, mac: MacAddr) -> bool {
+        mac.octets().starts_with(self.as_bytes())
+    }
+}
+
+impl FromStr for MacPrefix {
+    type Err = String;
+
+    /// 解析 `52:54:00` 这样的前缀，或 `qemu`、`xen` 等预设名称。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        let bytes: Vec<u8> = match PRESETS.iter().find(|(name, _)| *name == lower) {
+            Some((_, bytes)) => bytes.to_vec(),
+            None => lower
+                .split([':', '-'])
+                .map(|part| {
+                    if part.is_empty()
+                        || part.len() > 2
+                        || !part.bytes().all(|b| b.is_ascii_hexdigit())
+                    {
+                        return Err(format!("无效的前缀分组 '{}'", part));
+                    }
+                    u8::from_str_radix(part, 16).map_err(|e| e.to_string())
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|e| {
+                    let presets: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+                    format!(
+                        "无效的MAC前缀 '{}': {}。期望 1~5 组十六进制 (例如 52:54:00) 或预设名称: {}",
+                        s,
+                        e,
+                        presets.join(", ")
+                    )
+                })?,
+        };
+
+        if bytes.is_empty() || bytes.len() > 5 {
+            return Err(format!("MAC前缀 '{}' 的长度应为 1~5 字节", s));
+        }
+        if bytes[0] & 0x01 != 0 {
+            return Err(format!(
+                "MAC前缀 '{}' 的第一个字节置位了最低位 (0x01, I/G 位)，生成的会是组播地址",
+                s
+            ));
+        }
+
+        let mut prefix = MacPrefix {
+            bytes: [0; 5],
+            len: bytes.len(),
+        };
+        prefix.bytes[..bytes.len()].copy_from_slice(&bytes);
+        Ok(prefix)
+    }
+}
+
+impl fmt::Display for MacPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        f.write_str(&parts.join(":"))
+    }
+}
+
+impl<'de> Deserialize<'de> for MacPrefix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
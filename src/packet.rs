@@ -0,0 +1,206 @@
+//! 以太网帧的构造。
+
+use crate::mac::MacAddr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+pub const ETHERTYPE_VLAN: u16 = 0x8100;
+pub const ETHERTYPE_IPV6: u16 = 0x86dd;
+
+pub const ETHERNET_HEADER_LEN: usize = 14;
+
+pub const ARP_REQUEST: u16 = 1;
+pub const ARP_REPLY: u16 = 2;
+
+pub const IPPROTO_ICMPV6: u8 = 58;
+pub const ICMPV6_NEIGHBOR_SOLICITATION: u8 = 135;
+pub const ICMPV6_NEIGHBOR_ADVERTISEMENT: u8 = 136;
+
+/// NA 标志: 对 NS 的应答
+pub const NA_FLAG_SOLICITED: u32 = 0x4000_0000;
+/// NA 标志: 覆盖已有缓存条目
+pub const NA_FLAG_OVERRIDE: u32 = 0x2000_0000;
+
+/// 所有节点组播地址 ff02::1
+pub const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+/// 写入以太网首部。
+fn ethernet(dst: MacAddr, src: MacAddr, ethertype: u16, payload_len: usize) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(ETHERNET_HEADER_LEN + payload_len);
+    frame.extend_from_slice(&dst.octets());
+    frame.extend_from_slice(&src.octets());
+    frame.extend_from_slice(&ethertype.to_be_bytes());
+    frame
+}
+
+/// IPv6 组播地址对应的以太网组播地址 (33:33 加地址的低 32 位)。
+pub fn ipv6_multicast_mac(addr: Ipv6Addr) -> MacAddr {
+    let o = addr.octets();
+    MacAddr::new([0x33, 0x33, o[12], o[13], o[14], o[15]])
+}
+
+/// 构造一个 ARP 帧 (以太网 + IPv4)。
+pub fn arp(
+    eth_dst: MacAddr,
+    op: u16,
+    sender_mac: MacAddr,
+    sender_ip: Ipv4Addr,
+    target_mac: MacAddr,
+    target_ip: Ipv4Addr,
+) -> Vec<u8> {
+    let mut frame = ethernet(eth_dst, sender_mac, ETHERTYPE_ARP, 28);
+    frame.extend_from_slice(&1u16.to_be_bytes()); // 硬件类型: 以太网
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.push(6);
+    frame.push(4);
+    frame.extend_from_slice(&op.to_be_bytes());
+    frame.extend_from_slice(&sender_mac.octets());
+    frame.extend_from_slice(&sender_ip.octets());
+    frame.extend_from_slice(&target_mac.octets());
+    frame.extend_from_slice(&target_ip.octets());
+    frame
+}
+
+/// 免费 ARP (RFC 5227 的 ARP Announcement)：发送方与目标 IP 都是 `ip`。
+pub fn gratuitous_arp(mac: MacAddr, ip: Ipv4Addr) -> Vec<u8> {
+    arp(MacAddr::BROADCAST, ARP_REQUEST, mac, ip, MacAddr::ZERO, ip)
+}
+
+/// 构造一个携带 ICMPv6 报文的 IPv6 帧，并填好 ICMPv6 校验和。
+fn icmpv6(
+    eth_dst: MacAddr,
+    eth_src: MacAddr,
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    mut icmp: Vec<u8>,
+) -> Vec<u8> {
+    let checksum = icmpv6_checksum(src, dst, &icmp);
+    icmp[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+    let mut frame = ethernet(eth_dst, eth_src, ETHERTYPE_IPV6, 40 + icmp.len());
+    frame.extend_from_slice(&[0x60, 0, 0, 0]); // 版本 6，无流量类别和流标签
+    frame.extend_from_slice(&(icmp.len() as u16).to_be_bytes());
+    frame.push(IPPROTO_ICMPV6);
+    frame.push(255); // ND 报文要求跳数限制为 255
+    frame.extend_from_slice(&src.octets());
+    frame.extend_from_slice(&dst.octets());
+    frame.extend_from_slice(&icmp);
+    frame
+}
+
+/// 构造邻居通告 (NA)，附带目标链路层地址选项。
+pub fn neighbor_advertisement(
+    eth_dst: MacAddr,
+    mac: MacAddr,
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    target: Ipv6Addr,
+    flags: u32,
+) -> Vec<u8> {
+    let mut icmp = vec![ICMPV6_NEIGHBOR_ADVERTISEMENT, 0, 0, 0];
+    icmp.extend_from_slice(&flags.to_be_bytes());
+    icmp.extend_from_slice(&target.octets());
+    icmp.extend_from_slice(&[2, 1]); // 选项: 目标链路层地址，长度 1 (8 字节)
+    icmp.extend_from_slice(&mac.octets());
+    icmpv6(eth_dst, mac, src, dst, icmp)
+}
+
+/// 主动发往所有节点的邻居通告 (RFC 4861 7.2.6)。
+pub fn unsolicited_na(mac: MacAddr, ip: Ipv6Addr) -> Vec<u8> {
+    neighbor_advertisement(
+        ipv6_multicast_mac(ALL_NODES),
+        mac,
+        ip,
+        ALL_NODES,
+        ip,
+        NA_FLAG_OVERRIDE,
+    )
+}
+
+/// 按 RFC 1071 累加 16 位字。
+fn checksum_add(mut sum: u32, data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+    sum
+}
+
+fn checksum_fold(mut sum: u32) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// 含 IPv6 伪首部的 ICMPv6 校验和。
+pub fn icmpv6_checksum(src: Ipv6Addr, dst: Ipv6Addr, icmp: &[u8]) -> u16 {
+    let mut sum = checksum_add(0, &src.octets());
+    sum = checksum_add(sum, &dst.octets());
+    sum = checksum_add(sum, &(icmp.len() as u32).to_be_bytes());
+    sum = checksum_add(sum, &[0, 0, 0, IPPROTO_ICMPV6]);
+    sum = checksum_add(sum, icmp);
+    checksum_fold(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: MacAddr = MacAddr::new([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+    const PEER: MacAddr = MacAddr::new([0x52, 0x54, 0x00, 0xab, 0xcd, 0xef]);
+
+    fn ip(s: &str) -> Ipv6Addr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn neighbor_advertisement_checksum_verifies() {
+        let (src, dst) = (ip("fd00::1"), ip("fd00::2"));
+        let frame = neighbor_advertisement(PEER, MAC, src, dst, src, NA_FLAG_SOLICITED);
+        let icmp = &frame[ETHERNET_HEADER_LEN + 40..];
+        // 连同校验和一起重新求和时结果为 0xffff，取反后为 0
+        assert_eq!(icmpv6_checksum(src, dst, icmp), 0);
+    }
+
+    #[test]
+    fn neighbor_advertisement_layout() {
+        let (src, dst) = (ip("fd00::1"), ip("fd00::2"));
+        let frame = neighbor_advertisement(PEER, MAC, src, dst, src, NA_FLAG_SOLICITED);
+        assert_eq!(&frame[0..6], &PEER.octets());
+        assert_eq!(&frame[6..12], &MAC.octets());
+        assert_eq!(&frame[12..14], &ETHERTYPE_IPV6.to_be_bytes());
+
+        let header = &frame[ETHERNET_HEADER_LEN..ETHERNET_HEADER_LEN + 40];
+        assert_eq!(u16::from_be_bytes([header[4], header[5]]), 32);
+        assert_eq!(header[6], IPPROTO_ICMPV6);
+        assert_eq!(header[7], 255);
+
+        let icmp = &frame[ETHERNET_HEADER_LEN + 40..];
+        assert_eq!(icmp[0], ICMPV6_NEIGHBOR_ADVERTISEMENT);
+        assert_eq!(&icmp[4..8], &NA_FLAG_SOLICITED.to_be_bytes());
+        assert_eq!(&icmp[8..24], &src.octets());
+        // 目标链路层地址选项: 类型 2，长度 1，随后是 MAC 地址
+        assert_eq!(&icmp[24..26], &[2, 1]);
+        assert_eq!(&icmp[26..32], &MAC.octets());
+    }
+
+    #[test]
+    fn unsolicited_na_goes_to_all_nodes() {
+        let target = ip("fd00::1");
+        let frame = unsolicited_na(MAC, target);
+        assert_eq!(&frame[0..6], &[0x33, 0x33, 0, 0, 0, 1]);
+        assert_eq!(
+            &frame[ETHERNET_HEADER_LEN + 24..ETHERNET_HEADER_LEN + 40],
+            &ALL_NODES.octets()
+        );
+
+        let icmp = &frame[ETHERNET_HEADER_LEN + 40..];
+        assert_eq!(&icmp[4..8], &NA_FLAG_OVERRIDE.to_be_bytes());
+        assert_eq!(icmpv6_checksum(target, ALL_NODES, icmp), 0);
+    }
+}
@@ -0,0 +1,132 @@
+//! 多队列设备的读取任务与每队列计数。
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tun_rs::AsyncDevice;
+
+/// 单次读取的缓冲区大小，足以容纳任何 MTU 下的一帧。
+const READ_BUFFER_SIZE: usize = 65536;
+
+/// 一个队列的计数器。
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    pub packets: AtomicU64,
+    pub bytes: AtomicU64,
+    pub errors: AtomicU64,
+}
+
+impl QueueStats {
+    fn record(&self, len: usize) {
+        self.packets.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+}
+
+/// 对队列上读到的每一帧执行的处理，例如写入捕获文件或应答 ARP 请求。
+pub trait FrameHandler: Send + Sync {
+    /// 处理一帧，返回需要从同一队列写回设备的应答帧。
+    fn handle(&self, frame: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// 读取开始前需要写入设备的帧，例如免费 ARP。
+    fn startup(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(Vec::new())
+    }
+}
+
+/// 同一接口上的一个队列。
+pub struct Queue {
+    pub id: usize,
+    pub device: Arc<AsyncDevice>,
+    pub stats: Arc<QueueStats>,
+}
+
+/// 在已创建的设备上再打开 `count - 1` 个队列，返回全部 `count` 个队列。
+///
+/// 设备必须以 `multi_queue(true)` 创建，否则内核会拒绝附加新队列。
+pub fn open_queues(device: AsyncDevice, count: usize) -> Result<Vec<Queue>> {
+    let device = Arc::new(device);
+    let mut queues = Vec::with_capacity(count);
+    for id in 0..count {
+        let device = if id == 0 {
+            device.clone()
+        } else {
+            Arc::new(
+                device
+                    .try_clone()
+                    .with_context(|| format!("打开队列 #{} 失败", id))?,
+            )
+        };
+        queues.push(Queue {
+            id,
+            device,
+            stats: Arc::default(),
+        });
+    }
+    Ok(queues)
+}
+
+impl Queue {
+    /// 把每个处理器的启动帧写入该队列。
+    pub async fn send_startup(&self, handlers: &[Box<dyn FrameHandler>]) -> Result<()> {
+        for handler in handlers {
+            for frame in handler.startup()? {
+                self.device
+                    .send(&frame)
+                    .await
+                    .with_context(|| format!("向队列 #{} 写入启动帧失败", self.id))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 持续读取该队列上的帧，计数并依次交给 `handlers`，直到读取或处理出错。
+    pub async fn run(&self, handlers: &[Box<dyn FrameHandler>]) -> Result<()> {
+        let mut buf = vec![0u8; READ_BUFFER_SIZE];
+        loop {
+            match self.device.recv(&mut buf).await {
+                Ok(len) => {
+                    self.stats.record(len);
+                    debug!("队列 #{}: 收到 {} 字节", self.id, len);
+                    for handler in handlers {
+                        if let Some(reply) = handler.handle(&buf[..len])? {
+                            self.device
+                                .send(&reply)
+                                .await
+                                .with_context(|| format!("向队列 #{} 写入应答失败", self.id))?;
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.stats.errors.fetch_add(1, Ordering::Relaxed);
+                    return Err(e).with_context(|| format!("从队列 #{} 读取失败", self.id));
+                }
+            }
+        }
+    }
+}
+
+/// 打印每个队列的计数及其在总包数中所占的比例，`stats` 按队列编号排列。
+pub fn log_stats(stats: &[Arc<QueueStats>]) {
+    let total: u64 = stats
+        .iter()
+        .map(|s| s.packets.load(Ordering::Relaxed))
+        .sum();
+    for (id, stats) in stats.iter().enumerate() {
+        let packets = stats.packets.load(Ordering::Relaxed);
+        let share = if total == 0 {
+            0.0
+        } else {
+            packets as f64 * 100.0 / total as f64
+        };
+        info!(
+            "队列 #{}: {} 个包 ({:.1}%)，{} 字节，{} 次错误",
+            id,
+            packets,
+            share,
+            stats.bytes.load(Ordering::Relaxed),
+            stats.errors.load(Ordering::Relaxed)
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! 定时轮换MAC地址，用于模拟客户端的MAC随机化。
+
+use crate::change::change_mac;
+use crate::mac::{generate_random_mac, MacAddr};
+use crate::netlink::Netlink;
+use crate::oui::MacPrefix;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+/// 历史文件中的一条记录 (每行一个 JSON 对象)。
+#[derive(Serialize)]
+struct HistoryEntry<'a> {
+    timestamp: String,
+    interface: &'a str,
+    old: Option<MacAddr>,
+    new: MacAddr,
+}
+
+/// MAC 地址轮换的配置。
+pub struct MacRotation {
+    pub interval: Duration,
+    /// 每次生成新地址时保留的厂商前缀
+    pub prefix: Option<MacPrefix>,
+    /// 追加写入每次轮换记录的文件
+    pub history: Option<PathBuf>,
+}
+
+impl MacRotation {
+    /// 每隔 `interval` 为接口 `name` 更换一个新的本地管理地址，永不返回，除非历史文件无法打开。
+    pub async fn run(&self, netlink: Netlink, name: String) -> Result<()> {
+        let mut history = self
+            .history
+            .as_ref()
+            .map(|path| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("打开MAC历史文件 '{}' 失败", path.display()))
+            })
+            .transpose()?;
+
+        info!(
+            "每 {} 轮换一次设备 '{}' 的MAC地址",
+            humantime::format_duration(self.interval),
+            name
+        );
+        let mut ticker = interval_at(Instant::now() + self.interval, self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+
+            let mut new_mac = generate_random_mac();
+            if let Some(prefix) = self.prefix {
+                new_mac = prefix.apply(new_mac);
+            }
+            match change_mac(&netlink, &name, new_mac).await {
+                Ok(old_mac) => {
+                    let timestamp = humantime::format_rfc3339_millis(SystemTime::now()).to_string();
+                    info!(
+                        "[{}] 设备 '{}' 的MAC地址已轮换: {} -> {}",
+                        timestamp,
+                        name,
+                        old_mac.map(|m| m.to_string()).unwrap_or_default(),
+                        new_mac
+                    );
+                    if let Some(file) = history.as_mut() {
+                        let entry = HistoryEntry {
+                            timestamp,
+                            interface: &name,
+                            old: old_mac,
+                            new: new_mac,
+                        };
+                        if let Err(e) = append_history(file, &entry) {
+                            warn!("写入MAC历史文件失败: {}", e);
+                        }
+                    }
+                }
+                Err(e) => warn!("轮换设备 '{}' 的MAC地址失败: {:#}", name, e),
+            }
+        }
+    }
+}
+
+fn append_history(file: &mut File, entry: &HistoryEntry) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.flush()
+}
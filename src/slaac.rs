@@ -0,0 +1,74 @@
+//! 校验内核按 SLAAC 生成的链路本地地址是否与MAC地址一致。
+
+use crate::mac::MacAddr;
+use crate::netlink::Netlink;
+use anyhow::Result;
+use std::fs;
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::Duration;
+
+/// 内核链路本地地址与 EUI-64 预期的比对结果。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkLocalCheck {
+    /// 接口上存在由MAC地址派生的链路本地地址
+    Match,
+    /// 接口上有链路本地地址，但没有一个与预期一致
+    Mismatch(Vec<Ipv6Addr>),
+    /// 等待超时后接口上仍没有链路本地地址
+    Missing,
+}
+
+/// 以 `xxxx:xxxx:xxxx:xxxx` 形式显示 EUI-64 接口标识。
+pub fn format_interface_id(id: [u8; 8]) -> String {
+    id.chunks(2)
+        .map(|pair| format!("{:02x}{:02x}", pair[0], pair[1]))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 是否为 fe80::/10 链路本地地址。
+pub(crate) fn is_link_local(addr: &Ipv6Addr) -> bool {
+    addr.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// 读取接口的 IPv6 地址生成模式 (0 = EUI-64, 1 = 不生成, 2/3 = stable-privacy/random)。
+pub fn addr_gen_mode(name: &str) -> Option<u8> {
+    fs::read_to_string(format!("/proc/sys/net/ipv6/conf/{}/addr_gen_mode", name))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// 在 `timeout` 内轮询接口 `index` 的地址，比对链路本地地址是否由 `mac` 派生。
+pub async fn verify_link_local(
+    netlink: &Netlink,
+    index: u32,
+    mac: MacAddr,
+    timeout: Duration,
+) -> Result<LinkLocalCheck> {
+    let expected = mac.ipv6_link_local();
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let link_locals: Vec<Ipv6Addr> = netlink
+            .addresses(index)
+            .await?
+            .into_iter()
+            .filter_map(|a| match a.address {
+                IpAddr::V6(v6) if is_link_local(&v6) => Some(v6),
+                _ => None,
+            })
+            .collect();
+        if link_locals.contains(&expected) {
+            return Ok(LinkLocalCheck::Match);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(if link_locals.is_empty() {
+                LinkLocalCheck::Missing
+            } else {
+                LinkLocalCheck::Mismatch(link_locals)
+            });
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
@@ -0,0 +1,81 @@
+//! 按接口名持久化已生成的MAC地址。
+
+use crate::mac::MacAddr;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 状态文件的内容。扩展名为 `.toml` 时以 TOML 存储，否则以 JSON 存储。
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MacState {
+    #[serde(default)]
+    interfaces: BTreeMap<String, MacAddr>,
+}
+
+/// 加了独占锁的状态文件，锁在 drop 时释放。
+pub struct MacStateFile {
+    file: File,
+    toml: bool,
+    state: MacState,
+}
+
+impl MacStateFile {
+    /// 打开 (必要时创建) 状态文件并加独占锁。
+    ///
+    /// 多个进程共享同一个状态文件时，后来者会阻塞直到前一个释放锁。
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("打开MAC状态文件 '{}' 失败", path.display()))?;
+        file.lock()
+            .with_context(|| format!("锁定MAC状态文件 '{}' 失败", path.display()))?;
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .with_context(|| format!("读取MAC状态文件 '{}' 失败", path.display()))?;
+
+        let toml = path.extension().is_some_and(|ext| ext == "toml");
+        let state = if content.trim().is_empty() {
+            MacState::default()
+        } else if toml {
+            toml::from_str(&content)
+                .with_context(|| format!("解析MAC状态文件 '{}' 失败", path.display()))?
+        } else {
+            serde_json::from_str(&content)
+                .with_context(|| format!("解析MAC状态文件 '{}' 失败", path.display()))?
+        };
+
+        Ok(MacStateFile { file, toml, state })
+    }
+
+    pub fn get(&self, name: &str) -> Option<MacAddr> {
+        self.state.interfaces.get(name).copied()
+    }
+
+    /// 记录 `name` 的MAC地址并立即写回文件。
+    pub fn insert(&mut self, name: &str, mac: MacAddr) -> Result<()> {
+        self.state.interfaces.insert(name.to_string(), mac);
+        self.save()
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let content = if self.toml {
+            toml::to_string_pretty(&self.state)?
+        } else {
+            serde_json::to_string_pretty(&self.state)? + "\n"
+        };
+        // 在持有锁的同一个文件句柄上原地重写，避免换掉 inode 后锁失效
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(content.as_bytes())?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! 直接调用内核接口的 Linux 辅助函数。
+
+use crate::mac::MacAddr;
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// 构造一个填好接口名的 `ifreq`。
+fn ifreq(name: &str) -> io::Result<libc::ifreq> {
+    if name.is_empty() || name.len() >= libc::IFNAMSIZ {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("接口名 '{}' 长度应为 1~{}", name, libc::IFNAMSIZ - 1),
+        ));
+    }
+    // SAFETY: ifreq 是纯 C 结构体，全零是合法值
+    let mut req: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in req.ifr_name.iter_mut().zip(name.bytes()) {
+        *dst = src as libc::c_char;
+    }
+    Ok(req)
+}
+
+/// 用于 SIOCxIFxxx ioctl 的控制套接字。
+fn control_socket() -> io::Result<OwnedFd> {
+    // SAFETY: socket 只读取整型参数
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd 是刚创建且有效的描述符
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// 查询接口 `name` 的索引号。
+pub fn if_index(name: &str) -> io::Result<u32> {
+    let name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: name 是以 NUL 结尾的有效 C 字符串
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => Err(io::Error::last_os_error()),
+        index => Ok(index),
+    }
+}
+
+/// 对接口 `name` 执行一次 ioctl，返回内核填写后的 `ifreq`。
+fn interface_ioctl(name: &str, request: libc::Ioctl) -> io::Result<libc::ifreq> {
+    let socket = control_socket()?;
+    let mut req = ifreq(name)?;
+    // SAFETY: req 是有效的 ifreq，生命周期覆盖整个调用
+    if unsafe { libc::ioctl(socket.as_raw_fd(), request, &mut req) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(req)
+}
+
+/// 通过 SIOCGIFHWADDR 读取内核中接口 `name` 当前的硬件地址。
+pub fn hardware_address(name: &str) -> io::Result<MacAddr> {
+    let req = interface_ioctl(name, libc::SIOCGIFHWADDR as libc::Ioctl)?;
+    // SAFETY: SIOCGIFHWADDR 成功后内核填写的是 ifru_hwaddr
+    let data = unsafe { req.ifr_ifru.ifru_hwaddr.sa_data };
+    let mut mac = [0u8; 6];
+    for (dst, src) in mac.iter_mut().zip(data.iter()) {
+        *dst = *src as u8;
+    }
+    Ok(MacAddr::new(mac))
+}
+
+/// 通过 SIOCGIFMTU 读取内核中接口 `name` 当前的 MTU。
+pub fn mtu(name: &str) -> io::Result<u32> {
+    let req = interface_ioctl(name, libc::SIOCGIFMTU as libc::Ioctl)?;
+    // SAFETY: SIOCGIFMTU 成功后内核填写的是 ifru_mtu
+    Ok(unsafe { req.ifr_ifru.ifru_mtu } as u32)
+}
+
+/// 通过 AF_PACKET 套接字从接口 `index` 发出一个完整的以太网帧。
+///
+/// 帧由内核从该接口发送出去，就像网卡自己发出的一样；对 TAP 设备而言，
+/// 这个帧会出现在设备的读端。
+pub fn send_frame(index: u32, frame: &[u8]) -> io::Result<()> {
+    if frame.len() < 14 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "以太网帧过短"));
+    }
+    // SAFETY: socket 只读取整型参数
+    let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd 是刚创建且有效的描述符
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: sockaddr_ll 是纯 C 结构体，全零是合法值
+    let mut addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+    addr.sll_family = libc::AF_PACKET as u16;
+    addr.sll_ifindex = index as i32;
+    addr.sll_halen = 6;
+    addr.sll_addr[..6].copy_from_slice(&frame[..6]);
+
+    // SAFETY: frame 与 addr 在调用期间都有效，长度参数与之一致
+    let sent = unsafe {
+        libc::sendto(
+            socket.as_raw_fd(),
+            frame.as_ptr().cast(),
+            frame.len(),
+            0,
+            (&addr as *const libc::sockaddr_ll).cast(),
+            std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
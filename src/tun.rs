@@ -0,0 +1,133 @@
+//! TUN/TAP 设备特有的内核接口: 持久化、所有者，以及 sysfs 中的设备标志。
+
+use crate::layer::DeviceLayer;
+use anyhow::{bail, Context, Result};
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::os::fd::AsRawFd;
+
+/// 在 TUN/TAP 设备的描述符上执行一个以整数为参数的 ioctl。
+fn tun_ioctl(device: &impl AsRawFd, request: libc::Ioctl, arg: libc::c_ulong) -> io::Result<()> {
+    // SAFETY: 这些 TUNSETxxx 请求的参数按值传递，不涉及指针
+    if unsafe { libc::ioctl(device.as_raw_fd(), request, arg) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 通过 TUNSETPERSIST 设置设备在最后一个描述符关闭后是否保留。
+pub fn set_persist(device: &impl AsRawFd, persist: bool) -> io::Result<()> {
+    tun_ioctl(device, libc::TUNSETPERSIST, persist as libc::c_ulong)
+}
+
+/// 通过 TUNSETOWNER 允许用户 `uid` 在没有 CAP_NET_ADMIN 的情况下附加到设备。
+pub fn set_owner(device: &impl AsRawFd, uid: u32) -> io::Result<()> {
+    tun_ioctl(device, libc::TUNSETOWNER, uid as libc::c_ulong)
+}
+
+/// 通过 TUNSETGROUP 允许组 `gid` 的成员在没有 CAP_NET_ADMIN 的情况下附加到设备。
+pub fn set_group(device: &impl AsRawFd, gid: u32) -> io::Result<()> {
+    tun_ioctl(device, libc::TUNSETGROUP, gid as libc::c_ulong)
+}
+
+/// 读取 `/sys/class/net/<name>/tun_flags`。接口不是 TUN/TAP 设备时返回 `None`。
+pub fn tun_flags(name: &str) -> Option<u32> {
+    let flags = fs::read_to_string(format!("/sys/class/net/{}/tun_flags", name)).ok()?;
+    u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// 由 `tun_flags` 判断设备的层。
+pub fn device_layer(flags: u32) -> DeviceLayer {
+    if flags & libc::IFF_TAP as u32 != 0 {
+        DeviceLayer::L2
+    } else {
+        DeviceLayer::L3
+    }
+}
+
+/// 确认接口 `name` 是持久化的 TUN/TAP 设备，可以安全地删除。
+pub fn check_deletable(name: &str) -> Result<()> {
+    let Some(flags) = tun_flags(name) else {
+        bail!("'{}' 不存在或不是 TUN/TAP 设备", name);
+    };
+    if flags & libc::IFF_PERSIST as u32 == 0 {
+        bail!(
+            "设备 '{}' 不是持久设备，它会在创建它的进程退出时自动删除",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// 解析 `--owner`: 用户名或数字 UID。
+pub fn parse_user(s: &str) -> Result<u32, String> {
+    if let Ok(uid) = s.parse() {
+        return Ok(uid);
+    }
+    let name = CString::new(s).map_err(|_| format!("无效的用户名 '{}'", s))?;
+    // SAFETY: name 是以 NUL 结尾的有效 C 字符串；返回的指针只在本次调用后立即读取
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+    if passwd.is_null() {
+        return Err(format!("用户 '{}' 不存在", s));
+    }
+    // SAFETY: passwd 非空，指向 libc 内部的静态 passwd 结构
+    Ok(unsafe { (*passwd).pw_uid })
+}
+
+/// 解析 `--group`: 组名或数字 GID。
+pub fn parse_group(s: &str) -> Result<u32, String> {
+    if let Ok(gid) = s.parse() {
+        return Ok(gid);
+    }
+    let name = CString::new(s).map_err(|_| format!("无效的组名 '{}'", s))?;
+    // SAFETY: name 是以 NUL 结尾的有效 C 字符串；返回的指针只在本次调用后立即读取
+    let group = unsafe { libc::getgrnam(name.as_ptr()) };
+    if group.is_null() {
+        return Err(format!("组 '{}' 不存在", s));
+    }
+    // SAFETY: group 非空，指向 libc 内部的静态 group 结构
+    Ok(unsafe { (*group).gr_gid })
+}
+
+/// 用户名，查不到时返回数字 UID。
+pub fn user_name(uid: u32) -> String {
+    // SAFETY: getpwuid 只读取整型参数；返回的指针只在本次调用后立即读取
+    let passwd = unsafe { libc::getpwuid(uid) };
+    if passwd.is_null() {
+        return uid.to_string();
+    }
+    // SAFETY: passwd 非空，pw_name 是以 NUL 结尾的 C 字符串
+    unsafe { CStr::from_ptr((*passwd).pw_name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 组名，查不到时返回数字 GID。
+pub fn group_name(gid: u32) -> String {
+    // SAFETY: getgrgid 只读取整型参数；返回的指针只在本次调用后立即读取
+    let group = unsafe { libc::getgrgid(gid) };
+    if group.is_null() {
+        return gid.to_string();
+    }
+    // SAFETY: group 非空，gr_name 是以 NUL 结尾的 C 字符串
+    unsafe { CStr::from_ptr((*group).gr_name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 为刚创建的设备设置所有者和组，再将其设为持久设备。
+pub fn make_persistent(
+    device: &impl AsRawFd,
+    name: &str,
+    owner: Option<u32>,
+    group: Option<u32>,
+) -> Result<()> {
+    if let Some(uid) = owner {
+        set_owner(device, uid).with_context(|| format!("设置设备 '{}' 的所有者失败", name))?;
+    }
+    if let Some(gid) = group {
+        set_group(device, gid).with_context(|| format!("设置设备 '{}' 的组失败", name))?;
+    }
+    set_persist(device, true).with_context(|| format!("将设备 '{}' 设为持久设备失败", name))
+}